@@ -1 +1,5 @@
-import("../pkg/index.js").catch(console.error);
+import("../pkg/index.js")
+  .then((module) => {
+    window.walkTheDog = module;
+  })
+  .catch(console.error);
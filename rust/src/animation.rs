@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::engine::Sheet;
+
+const DEFAULT_FRAME_TICKS: u16 = 3;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayMode {
+    #[default]
+    Loop,
+    Once,
+    PingPong,
+}
+
+/// How a clip plays, set per clip under `meta.animations` in the sheet's JSON.
+/// The keys are snake_case like the game's other data files, not camelCase
+/// like the rest of the exported sheet.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ClipSettings {
+    pub frame_ticks: u16,
+    pub mode: PlayMode,
+}
+
+impl Default for ClipSettings {
+    fn default() -> Self {
+        ClipSettings {
+            frame_ticks: DEFAULT_FRAME_TICKS,
+            mode: PlayMode::Loop,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// A looping or ping-pong clip went back to its first frame.
+    Looped,
+    /// A clip that plays once reached its last frame.
+    Completed,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationClip {
+    frames: Vec<String>,
+    // How long each frame shows, in ticks.
+    ticks: Vec<u16>,
+    mode: PlayMode,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<String>, settings: ClipSettings) -> Self {
+        let ticks = vec![settings.frame_ticks.max(1); frames.len()];
+        AnimationClip {
+            frames,
+            ticks,
+            mode: settings.mode,
+        }
+    }
+
+    /// A clip whose frames each show for their own number of ticks.
+    pub fn with_ticks(frames: Vec<(String, u16)>, mode: PlayMode) -> Self {
+        let (frames, ticks) = frames
+            .into_iter()
+            .map(|(frame, ticks)| (frame, ticks.max(1)))
+            .unzip();
+        AnimationClip {
+            frames,
+            ticks,
+            mode,
+        }
+    }
+
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+
+    pub fn mode(&self) -> PlayMode {
+        self.mode
+    }
+
+    // The frames in the order they play. A ping-pong clip goes there and back
+    // without showing either end twice.
+    fn steps(&self) -> impl Iterator<Item = usize> {
+        let back = match self.mode {
+            PlayMode::PingPong if self.frames.len() > 1 => 1..self.frames.len() - 1,
+            _ => 0..0,
+        };
+        (0..self.frames.len()).chain(back.rev())
+    }
+
+    /// Ticks to play the clip through once.
+    pub fn duration(&self) -> u16 {
+        self.steps().map(|index| self.ticks[index]).sum()
+    }
+
+    pub fn frame_at(&self, tick: u16) -> Option<&str> {
+        let duration = self.duration().max(1);
+        let mut tick = match self.mode {
+            PlayMode::Once => tick.min(duration - 1),
+            PlayMode::Loop | PlayMode::PingPong => tick % duration,
+        };
+        let index = self.steps().find(|&index| {
+            if tick < self.ticks[index] {
+                true
+            } else {
+                tick -= self.ticks[index];
+                false
+            }
+        })?;
+        self.frames.get(index).map(String::as_str)
+    }
+
+    /// The tick after `tick`, and whether the clip finished or started over on it.
+    /// A clip that plays once holds its last tick from then on.
+    pub fn advance(&self, tick: u16) -> (u16, Option<AnimationEvent>) {
+        let next = tick.saturating_add(1);
+        let last = self.duration().saturating_sub(1);
+        match self.mode {
+            PlayMode::Once if next >= last => (last, Some(AnimationEvent::Completed)),
+            PlayMode::Loop | PlayMode::PingPong if next >= self.duration() => {
+                (0, Some(AnimationEvent::Looped))
+            }
+            _ => (next, None),
+        }
+    }
+}
+
+/// Every clip in a sheet, found by grouping frames on the name before their
+/// number, so `Run (1).png` to `Run (8).png` make the `Run` clip, along with
+/// any the sheet lists itself.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Animations {
+    clips: HashMap<String, AnimationClip>,
+}
+
+impl Animations {
+    pub fn from_sheet(sheet: &Sheet) -> Self {
+        let mut numbered: HashMap<String, Vec<(u32, &str)>> = HashMap::new();
+        sheet.frames.keys().for_each(|name| {
+            let (clip, number) = clip_name(name);
+            numbered
+                .entry(clip.to_string())
+                .or_default()
+                .push((number, name));
+        });
+        let mut clips: HashMap<String, AnimationClip> = numbered
+            .into_iter()
+            .map(|(clip, mut frames)| {
+                frames.sort();
+                let settings = sheet
+                    .meta
+                    .animations
+                    .get(&clip)
+                    .copied()
+                    .unwrap_or_default();
+                let frames = frames
+                    .into_iter()
+                    .map(|(_number, name)| name.to_string())
+                    .collect();
+                (clip, AnimationClip::new(frames, settings))
+            })
+            .collect();
+        // Clips the sheet names itself, like Aseprite's tags, win over the guess.
+        clips.extend(sheet.clips.clone());
+        Animations { clips }
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.clips.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clips.is_empty()
+    }
+}
+
+// `Idle (10).png` is frame 10 of `Idle`, as is `Idle_10.png`. A name without a
+// number is a clip of its own.
+fn clip_name(frame: &str) -> (&str, u32) {
+    let stem = frame
+        .rsplit_once('.')
+        .map_or(frame, |(stem, _extension)| stem);
+    let numbered = stem.trim_end_matches(')');
+    let digits = numbered.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = numbered[digits.len()..].parse().unwrap_or(0);
+    match digits.trim_end_matches([' ', '_', '-', '(']) {
+        "" => (stem, number),
+        clip => (clip, number),
+    }
+}
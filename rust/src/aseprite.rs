@@ -0,0 +1,218 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::animation::{AnimationClip, PlayMode};
+use crate::engine::{Cell, Sheet, SheetMeta, SheetRect, FRAME_SIZE};
+
+const DEFAULT_DURATION: f32 = 100.0;
+
+fn default_duration() -> f32 {
+    DEFAULT_DURATION
+}
+
+#[derive(Deserialize)]
+struct Frame {
+    #[serde(flatten)]
+    cell: Cell,
+    /// Milliseconds.
+    #[serde(default = "default_duration")]
+    duration: f32,
+}
+
+#[derive(Deserialize)]
+struct NamedFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: Frame,
+}
+
+// Tags and slices refer to frames by their place in the file, so the frames
+// are kept in order whether they were exported as a hash or an array.
+struct Frames(Vec<(String, Frame)>);
+
+impl<'de> Deserialize<'de> for Frames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = Frames;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a hash or an array of frames")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Frames, A::Error> {
+                let mut frames = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    frames.push(entry);
+                }
+                Ok(Frames(frames))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> std::result::Result<Frames, A::Error> {
+                let mut frames = vec![];
+                while let Some(NamedFrame { filename, frame }) = seq.next_element()? {
+                    frames.push((filename, frame));
+                }
+                Ok(Frames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+#[derive(Deserialize)]
+struct FrameTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: Direction,
+    // Newer Aseprite versions write how many times a tag plays, as a string.
+    #[serde(default)]
+    repeat: Option<String>,
+}
+
+impl FrameTag {
+    fn clip(&self, frames: &[(String, Frame)]) -> Result<AnimationClip> {
+        let tagged = frames.get(self.from..=self.to).ok_or_else(|| {
+            anyhow!(
+                "Tag {} runs from frame {} to {}, but there are {} frames",
+                self.name,
+                self.from,
+                self.to,
+                frames.len()
+            )
+        })?;
+        let mut tagged: Vec<(String, u16)> = tagged
+            .iter()
+            .map(|(name, frame)| (name.clone(), ticks(frame.duration)))
+            .collect();
+        if matches!(
+            self.direction,
+            Direction::Reverse | Direction::PingpongReverse
+        ) {
+            tagged.reverse();
+        }
+        let mode = match (self.direction, self.repeat.as_deref()) {
+            (Direction::Pingpong | Direction::PingpongReverse, _) => PlayMode::PingPong,
+            (_, Some("1")) => PlayMode::Once,
+            _ => PlayMode::Loop,
+        };
+        Ok(AnimationClip::with_ticks(tagged, mode))
+    }
+}
+
+#[derive(Deserialize)]
+struct SliceKey {
+    frame: usize,
+    bounds: SheetRect,
+}
+
+#[derive(Deserialize)]
+struct Slice {
+    name: String,
+    keys: Vec<SliceKey>,
+}
+
+impl Slice {
+    // A key holds from its frame until the next key.
+    fn bounds_at(&self, frame: usize) -> Option<&SheetRect> {
+        self.keys
+            .iter()
+            .filter(|key| key.frame <= frame)
+            .max_by_key(|key| key.frame)
+            .map(|key| &key.bounds)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Meta {
+    #[serde(flatten)]
+    sheet: SheetMeta,
+    #[serde(default)]
+    frame_tags: Vec<FrameTag>,
+    #[serde(default)]
+    slices: Vec<Slice>,
+}
+
+#[derive(Deserialize)]
+struct Export {
+    frames: Frames,
+    meta: Meta,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Probe {
+    meta: ProbeMeta,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ProbeMeta {
+    app: String,
+}
+
+/// Whether `json` was exported by Aseprite, going by the app in its meta.
+pub fn is_export(json: &str) -> bool {
+    serde_json::from_str::<Probe>(json).is_ok_and(|probe| probe.meta.app.contains("aseprite.org"))
+}
+
+/// Turns Aseprite's exported JSON into a sheet. Each tag becomes a clip of
+/// the same name, timed by its frames' durations, and each slice is set on
+/// every frame it has a key for.
+pub fn sheet(json: &str) -> Result<Sheet> {
+    let export = serde_json::from_str::<Export>(json)
+        .map_err(|err| anyhow!("Could not parse Aseprite sheet {:#?}", err))?;
+    let mut frames = export.frames.0;
+    frames
+        .iter_mut()
+        .enumerate()
+        .for_each(|(index, (_name, frame))| {
+            export.meta.slices.iter().for_each(|slice| {
+                if let Some(bounds) = slice.bounds_at(index) {
+                    frame.cell.slices.insert(slice.name.clone(), bounds.clone());
+                }
+            })
+        });
+    let clips = export
+        .meta
+        .frame_tags
+        .iter()
+        .map(|tag| Ok((tag.name.clone(), tag.clip(&frames)?)))
+        .collect::<Result<_>>()?;
+    Ok(Sheet {
+        frames: frames
+            .into_iter()
+            .map(|(name, frame)| (name, frame.cell))
+            .collect(),
+        meta: export.meta.sheet,
+        clips,
+    })
+}
+
+fn ticks(duration: f32) -> u16 {
+    (duration / FRAME_SIZE).round() as u16
+}
@@ -0,0 +1,286 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde::Deserialize;
+
+use crate::browser;
+use crate::engine::{self, Audio, ImageHandle, Sheet, Sound, SpriteSheet};
+
+pub const MANIFEST: &str = "assets.json";
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum AssetSource {
+    Image { path: String },
+    Sheet { json: String, image: String },
+    Sound { path: String },
+    Text { path: String },
+}
+
+impl fmt::Display for AssetSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetSource::Image { path }
+            | AssetSource::Sound { path }
+            | AssetSource::Text { path } => write!(f, "{}", path),
+            AssetSource::Sheet { json, image } => write!(f, "{} + {}", json, image),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct AssetEntry {
+    pub key: String,
+    #[serde(flatten)]
+    pub source: AssetSource,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct AssetManifest {
+    pub assets: Vec<AssetEntry>,
+}
+
+impl AssetManifest {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|err| anyhow!("Could not parse asset manifest {:#?}", err))
+    }
+}
+
+/// Fetches the raw files behind the manifest, so the manager can be driven
+/// natively with headless assets.
+#[async_trait(?Send)]
+pub trait AssetLoader {
+    async fn load_image(&self, path: &str) -> Result<ImageHandle>;
+    async fn load_text(&self, path: &str) -> Result<String>;
+    async fn load_sound(&self, path: &str) -> Result<Sound>;
+}
+
+pub struct BrowserLoader {
+    audio: Audio,
+}
+
+impl BrowserLoader {
+    pub fn new(audio: Audio) -> Self {
+        BrowserLoader { audio }
+    }
+}
+
+#[async_trait(?Send)]
+impl AssetLoader for BrowserLoader {
+    async fn load_image(&self, path: &str) -> Result<ImageHandle> {
+        engine::load_image(path).await
+    }
+
+    async fn load_text(&self, path: &str) -> Result<String> {
+        browser::fetch_text(path).await
+    }
+
+    async fn load_sound(&self, path: &str) -> Result<Sound> {
+        self.audio.load_sound(path).await
+    }
+}
+
+#[derive(Clone)]
+enum Asset {
+    Image(ImageHandle),
+    Sheet(Sheet, ImageHandle),
+    Sound(Sound),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetError {
+    pub key: String,
+    pub source: String,
+    pub message: String,
+}
+
+/// Every asset that failed in one load, rather than just the first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetErrors(pub Vec<AssetError>);
+
+impl fmt::Display for AssetErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Could not load {} asset(s)", self.0.len())?;
+        self.0.iter().try_for_each(|error| {
+            write!(f, "\n{} ({}): {}", error.key, error.source, error.message)
+        })
+    }
+}
+
+impl std::error::Error for AssetErrors {}
+
+/// Shared with whoever draws the loading screen while the manager loads.
+#[derive(Clone, Default)]
+pub struct LoadProgress {
+    loaded: Rc<Cell<usize>>,
+    failed: Rc<Cell<usize>>,
+    total: Rc<Cell<usize>>,
+    in_flight: Rc<RefCell<Vec<String>>>,
+}
+
+impl LoadProgress {
+    pub fn new() -> Self {
+        LoadProgress::default()
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.loaded.get()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed.get()
+    }
+
+    pub fn total(&self) -> usize {
+        self.total.get()
+    }
+
+    /// Nothing counts as loaded until the manifest says how much there is.
+    pub fn fraction(&self) -> f32 {
+        match self.total() {
+            0 => 0.0,
+            total => self.loaded() as f32 / total as f32,
+        }
+    }
+
+    /// The files of the oldest asset still being fetched.
+    pub fn current(&self) -> Option<String> {
+        self.in_flight.borrow().first().cloned()
+    }
+
+    fn started(&self, source: &str) {
+        self.in_flight.borrow_mut().push(source.to_string());
+    }
+
+    fn finished(&self, source: &str, loaded: bool) {
+        self.in_flight
+            .borrow_mut()
+            .retain(|in_flight| in_flight != source);
+        if loaded {
+            self.loaded.set(self.loaded() + 1);
+        } else {
+            self.failed.set(self.failed() + 1);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AssetManager {
+    assets: HashMap<String, Asset>,
+}
+
+impl AssetManager {
+    pub fn new() -> Self {
+        AssetManager::default()
+    }
+
+    /// Loads every asset in `manifest` that isn't cached yet, all at once.
+    /// Assets that load are kept even if others fail, so calling this again
+    /// only retries the failures.
+    pub async fn load(
+        &mut self,
+        manifest: &AssetManifest,
+        loader: &dyn AssetLoader,
+        progress: &LoadProgress,
+    ) -> Result<()> {
+        let pending: Vec<&AssetEntry> = manifest
+            .assets
+            .iter()
+            .filter(|entry| !self.assets.contains_key(&entry.key))
+            .collect();
+        progress.total.set(manifest.assets.len());
+        progress.loaded.set(manifest.assets.len() - pending.len());
+        progress.failed.set(0);
+        progress.in_flight.borrow_mut().clear();
+        let results = join_all(pending.into_iter().map(|entry| async move {
+            let source = entry.source.to_string();
+            progress.started(&source);
+            let result = load_asset(&entry.source, loader).await;
+            progress.finished(&source, result.is_ok());
+            (entry, result)
+        }))
+        .await;
+        let errors: Vec<AssetError> = results
+            .into_iter()
+            .filter_map(|(entry, result)| match result {
+                Ok(asset) => {
+                    self.assets.insert(entry.key.clone(), asset);
+                    None
+                }
+                Err(err) => Some(AssetError {
+                    key: entry.key.clone(),
+                    source: entry.source.to_string(),
+                    message: format!("{:#}", err),
+                }),
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AssetErrors(errors).into())
+        }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.assets.contains_key(key)
+    }
+
+    pub fn image(&self, key: &str) -> Result<ImageHandle> {
+        match self.get(key)? {
+            Asset::Image(image) => Ok(image.clone()),
+            _ => Err(anyhow!("Asset {} is not an image", key)),
+        }
+    }
+
+    pub fn sheet(&self, key: &str) -> Result<(Sheet, ImageHandle)> {
+        match self.get(key)? {
+            Asset::Sheet(sheet, image) => Ok((sheet.clone(), image.clone())),
+            _ => Err(anyhow!("Asset {} is not a sprite sheet", key)),
+        }
+    }
+
+    pub fn sprite_sheet(&self, key: &str) -> Result<Rc<SpriteSheet>> {
+        let (sheet, image) = self.sheet(key)?;
+        Ok(Rc::new(SpriteSheet::new(sheet, image)))
+    }
+
+    pub fn sound(&self, key: &str) -> Result<Sound> {
+        match self.get(key)? {
+            Asset::Sound(sound) => Ok(sound.clone()),
+            _ => Err(anyhow!("Asset {} is not a sound", key)),
+        }
+    }
+
+    pub fn text(&self, key: &str) -> Result<&str> {
+        match self.get(key)? {
+            Asset::Text(text) => Ok(text),
+            _ => Err(anyhow!("Asset {} is not a text file", key)),
+        }
+    }
+
+    fn get(&self, key: &str) -> Result<&Asset> {
+        self.assets
+            .get(key)
+            .ok_or_else(|| anyhow!("Asset {} is not loaded", key))
+    }
+}
+
+async fn load_asset(source: &AssetSource, loader: &dyn AssetLoader) -> Result<Asset> {
+    match source {
+        AssetSource::Image { path } => Ok(Asset::Image(loader.load_image(path).await?)),
+        AssetSource::Sheet { json, image } => {
+            let (json, image) = futures::join!(loader.load_text(json), loader.load_image(image));
+            let sheet = Sheet::from_json(&json?)?;
+            Ok(Asset::Sheet(sheet, image?))
+        }
+        AssetSource::Sound { path } => Ok(Asset::Sound(loader.load_sound(path).await?)),
+        AssetSource::Text { path } => Ok(Asset::Text(loader.load_text(path).await?)),
+    }
+}
@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+
+use serde::Serialize;
+
+use crate::{
+    browser,
+    engine::KeyState,
+    replay::{InputMode, InputRecorder, Recording},
+};
+
+const BENCHMARK_PARAMETER: &str = "benchmark";
+pub const BENCHMARK_SEED: u32 = 1;
+const SCRIPTED_TICKS: u32 = 60 * 60;
+const JUMP_EVERY: u32 = 90;
+const JUMP_LENGTH: u32 = 6;
+
+thread_local! {
+    static LAST_REPORT: RefCell<Option<BenchmarkReport>> = const { RefCell::new(None) };
+}
+
+pub fn last_report() -> Option<BenchmarkReport> {
+    LAST_REPORT.with(|report| report.borrow().clone())
+}
+
+pub fn requested() -> bool {
+    browser::query_parameter(BENCHMARK_PARAMETER).is_some()
+}
+
+// Runs right from the first tick and taps jump on a fixed cadence, so both
+// builds can replay the exact same session when no recording is supplied.
+pub fn scripted_session(seed: u32) -> Recording {
+    let recorder = InputRecorder::new(seed);
+    (0..SCRIPTED_TICKS).for_each(|tick| {
+        let mut keystate = KeyState::new();
+        keystate.set_pressed("ArrowRight");
+        if tick % JUMP_EVERY < JUMP_LENGTH {
+            keystate.set_pressed("Space");
+        }
+        recorder.record(&keystate);
+    });
+    recorder.recording()
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    pub fn from_samples(samples: &[f64]) -> Self {
+        if samples.is_empty() {
+            return Summary::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let percentile = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Summary {
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50: percentile(50.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+// The TypeScript build fills the same fields, reading `performance.memory`
+// where this build reads the size of the wasm linear memory.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BenchmarkReport {
+    pub implementation: String,
+    pub seed: u32,
+    pub frames: usize,
+    pub update_ms: Summary,
+    pub draw_ms: Summary,
+    pub frame_ms: Summary,
+    /// Frames during which the heap neither grew nor was collected.
+    pub gc_free_frames: usize,
+    pub initial_memory_bytes: f64,
+    pub final_memory_bytes: f64,
+    pub memory_growth_bytes: f64,
+}
+
+impl BenchmarkReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+pub struct Benchmark {
+    seed: u32,
+    frames: usize,
+    update_ms: Vec<f64>,
+    draw_ms: Vec<f64>,
+    gc_free_frames: usize,
+    initial_memory_bytes: f64,
+    last_memory_bytes: f64,
+}
+
+impl Benchmark {
+    pub fn new(seed: u32, frames: usize, initial_memory_bytes: f64) -> Self {
+        Benchmark {
+            seed,
+            frames,
+            update_ms: Vec::with_capacity(frames),
+            draw_ms: Vec::with_capacity(frames),
+            gc_free_frames: 0,
+            initial_memory_bytes,
+            last_memory_bytes: initial_memory_bytes,
+        }
+    }
+
+    // Only a replayed session is comparable between builds, so live input never benchmarks.
+    pub fn for_input(input: &InputMode) -> Option<Self> {
+        match input {
+            InputMode::Replay(replay) if requested() => Some(Benchmark::new(
+                replay.seed(),
+                replay.ticks() as usize,
+                browser::memory_bytes().unwrap_or_default(),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn record_frame(&mut self, update_ms: f64, draw_ms: f64, memory_bytes: f64) {
+        self.update_ms.push(update_ms);
+        self.draw_ms.push(draw_ms);
+        if memory_bytes == self.last_memory_bytes {
+            self.gc_free_frames += 1;
+        }
+        self.last_memory_bytes = memory_bytes;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.update_ms.len() >= self.frames
+    }
+
+    pub fn report(&self) -> BenchmarkReport {
+        let frame_ms: Vec<f64> = self
+            .update_ms
+            .iter()
+            .zip(&self.draw_ms)
+            .map(|(update, draw)| update + draw)
+            .collect();
+        BenchmarkReport {
+            implementation: "rust".to_string(),
+            seed: self.seed,
+            frames: self.update_ms.len(),
+            update_ms: Summary::from_samples(&self.update_ms),
+            draw_ms: Summary::from_samples(&self.draw_ms),
+            frame_ms: Summary::from_samples(&frame_ms),
+            gc_free_frames: self.gc_free_frames,
+            initial_memory_bytes: self.initial_memory_bytes,
+            final_memory_bytes: self.last_memory_bytes,
+            memory_growth_bytes: self.last_memory_bytes - self.initial_memory_bytes,
+        }
+    }
+
+    pub fn publish(&self) {
+        let report = self.report();
+        log!("{}", report.to_json());
+        LAST_REPORT.with(|last| last.replace(Some(report)));
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use futures::Future;
-use js_sys::ArrayBuffer;
+use js_sys::{ArrayBuffer, WebAssembly};
 use wasm_bindgen::{
     closure::{WasmClosure, WasmClosureFnOnce},
     prelude::Closure,
@@ -8,17 +8,30 @@ use wasm_bindgen::{
 };
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    CanvasRenderingContext2d, Document, Element, HtmlCanvasElement, HtmlElement, HtmlImageElement,
-    Response, Window,
+    CanvasRenderingContext2d, Document, Element, Gamepad, HtmlCanvasElement, HtmlElement,
+    HtmlImageElement, HtmlInputElement, Response, UrlSearchParams, Window,
 };
 
+pub mod storage;
+
 macro_rules! log {
 	($($t:tt)*) => {
-		web_sys::console::log_1(&format!($($t)*).into())
+		$crate::browser::log(&format!($($t)*))
 	}
 }
 
+pub fn log(message: &str) {
+    if cfg!(target_arch = "wasm32") {
+        web_sys::console::log_1(&message.into());
+    } else {
+        eprintln!("{}", message);
+    }
+}
+
 pub fn window() -> Result<Window> {
+    if cfg!(not(target_arch = "wasm32")) {
+        return Err(anyhow!("No Window Found outside of the browser"));
+    }
     web_sys::window().ok_or_else(|| anyhow!("No Window Found"))
 }
 
@@ -81,6 +94,19 @@ pub async fn fetch_json(json_path: &str) -> Result<JsValue> {
     .map_err(|err| anyhow!("error fetching JSON {:#?}", err))
 }
 
+pub async fn fetch_text(resource: &str) -> Result<String> {
+    let resp = fetch_response(resource).await?;
+
+    JsFuture::from(
+        resp.text()
+            .map_err(|err| anyhow!("Could not get text from response {:#?}", err))?,
+    )
+    .await
+    .map_err(|err| anyhow!("error fetching text {:#?}", err))?
+    .as_string()
+    .ok_or_else(|| anyhow!("Response text was not a string"))
+}
+
 pub async fn fetch_array_buffer(resource: &str) -> Result<ArrayBuffer> {
     let array_buffer = fetch_response(resource)
         .await?
@@ -116,6 +142,22 @@ pub fn closure_wrap<T: WasmClosure + ?Sized>(data: Box<T>) -> Closure<T> {
     Closure::wrap(data)
 }
 
+// The events that let a page start audio.
+const GESTURE_EVENTS: [&str; 4] = ["pointerdown", "pointerup", "touchend", "keydown"];
+
+/// Calls `callback` on every user gesture for as long as the page lives.
+pub fn on_user_gesture(callback: impl FnMut() + 'static) -> Result<()> {
+    let window = window()?;
+    let callback = closure_wrap(Box::new(callback) as Box<dyn FnMut()>);
+    GESTURE_EVENTS.iter().try_for_each(|event| {
+        window
+            .add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
+            .map_err(|err| anyhow!("Could not listen for {} {:#?}", event, err))
+    })?;
+    callback.forget();
+    Ok(())
+}
+
 pub fn create_raf_closure(f: impl FnMut(f64) + 'static) -> LoopClosure {
     closure_wrap(Box::new(f))
 }
@@ -127,6 +169,52 @@ pub fn now() -> Result<f64> {
         .now())
 }
 
+// Disconnected slots come back as null and are skipped.
+pub fn gamepads() -> Result<Vec<Gamepad>> {
+    Ok(window()?
+        .navigator()
+        .get_gamepads()
+        .map_err(|err| anyhow!("Could not get gamepads {:#?}", err))?
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .collect())
+}
+
+pub fn memory_bytes() -> Result<f64> {
+    if cfg!(not(target_arch = "wasm32")) {
+        return Err(anyhow!("No wasm memory outside of the browser"));
+    }
+    wasm_bindgen::memory()
+        .dyn_into::<WebAssembly::Memory>()
+        .map_err(|err| anyhow!("Could not convert to WebAssembly.Memory {:#?}", err))?
+        .buffer()
+        .dyn_into::<ArrayBuffer>()
+        .map(|buffer| buffer.byte_length().into())
+        .map_err(|err| anyhow!("Could not convert memory buffer to ArrayBuffer {:#?}", err))
+}
+
+pub fn query_parameter(name: &str) -> Option<String> {
+    let search = window().ok()?.location().search().ok()?;
+    UrlSearchParams::new_with_str(&search).ok()?.get(name)
+}
+
+pub fn today() -> Result<String> {
+    if cfg!(not(target_arch = "wasm32")) {
+        return Err(anyhow!("No Date outside of the browser"));
+    }
+    let iso_date: String = js_sys::Date::new_0().to_iso_string().into();
+    Ok(iso_date.chars().take(10).collect())
+}
+
+pub fn input_value(id: &str) -> Result<String> {
+    document()?
+        .get_element_by_id(id)
+        .ok_or_else(|| anyhow!("Element with id {} not found", id))?
+        .dyn_into::<HtmlInputElement>()
+        .map(|input| input.value())
+        .map_err(|err| anyhow!("Could not cast into HtmlInputElement {:#?}", err))
+}
+
 pub fn draw_ui(html: &str) -> Result<()> {
     find_ui()?
         .insert_adjacent_html("afterbegin", html)
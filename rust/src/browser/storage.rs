@@ -0,0 +1,45 @@
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use web_sys::Storage;
+
+use super::window;
+
+fn local_storage() -> Result<Storage> {
+    window()?
+        .local_storage()
+        .map_err(|err| anyhow!("Could not access localStorage {:#?}", err))?
+        .ok_or_else(|| anyhow!("No localStorage Found"))
+}
+
+pub fn get_item(key: &str) -> Result<Option<String>> {
+    local_storage()?
+        .get_item(key)
+        .map_err(|err| anyhow!("Could not read {} from localStorage {:#?}", key, err))
+}
+
+pub fn set_item(key: &str, value: &str) -> Result<()> {
+    local_storage()?
+        .set_item(key, value)
+        .map_err(|err| anyhow!("Could not write {} to localStorage {:#?}", key, err))
+}
+
+pub fn get<T: DeserializeOwned>(key: &str) -> Result<Option<T>> {
+    get_item(key)?
+        .map(|json| {
+            serde_json::from_str(&json)
+                .map_err(|err| anyhow!("Could not parse {} from localStorage {:#?}", key, err))
+        })
+        .transpose()
+}
+
+pub fn set<T: Serialize>(key: &str, value: &T) -> Result<()> {
+    let json = serde_json::to_string(value)
+        .map_err(|err| anyhow!("Could not serialize {} {:#?}", key, err))?;
+    set_item(key, &json)
+}
+
+pub fn remove(key: &str) -> Result<()> {
+    local_storage()?
+        .remove_item(key)
+        .map_err(|err| anyhow!("Could not remove {} from localStorage {:#?}", key, err))
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+
+use crate::{
+    browser,
+    engine::{KeyState, Point, Rect, Renderer, TextAlign, TextStyle},
+};
+
+const DEBUG_PARAMETER: &str = "debug";
+/// The key left of 1, which no action is bound to by default.
+pub const DEBUG_KEY: &str = "Backquote";
+pub const OBSTACLE_COLOR: &str = "red";
+pub const PLAYER_COLOR: &str = "lime";
+const LINE_HEIGHT: i32 = 16;
+// Each frame's timing counts for this much of the average shown.
+const SMOOTHING: f64 = 0.1;
+
+const LINES_POSITION: Point = Point { x: 16, y: 60 };
+const TIMINGS_POSITION: Point = Point { x: 584, y: 544 };
+const DEBUG_STYLE: TextStyle = TextStyle {
+    font: "14px monospace",
+    color: "white",
+    align: TextAlign::Left,
+};
+const TIMINGS_STYLE: TextStyle = TextStyle {
+    align: TextAlign::Right,
+    ..DEBUG_STYLE
+};
+
+pub fn requested() -> bool {
+    browser::query_parameter(DEBUG_PARAMETER).is_some()
+}
+
+pub fn draw_bounding_boxes(renderer: &dyn Renderer, bounding_boxes: &[Rect], color: &str) {
+    bounding_boxes
+        .iter()
+        .for_each(|bounding_box| renderer.stroke_rect(bounding_box, color));
+}
+
+/// Lists `lines` down the left of the canvas, under the score.
+pub fn draw_lines(renderer: &dyn Renderer, lines: &[String]) -> Result<()> {
+    lines.iter().enumerate().try_for_each(|(index, line)| {
+        renderer.draw_text(
+            line,
+            &Point {
+                x: LINES_POSITION.x,
+                y: LINES_POSITION.y + index as i32 * LINE_HEIGHT,
+            },
+            &DEBUG_STYLE,
+        )
+    })
+}
+
+/// Hitboxes, game state and frame timings over the game, toggled with
+/// `DEBUG_KEY` or shown from the start with `?debug`.
+#[derive(Debug, Default)]
+pub struct DebugOverlay {
+    visible: bool,
+    key_held: bool,
+    update_ms: f64,
+    draw_ms: f64,
+}
+
+impl DebugOverlay {
+    pub fn new(visible: bool) -> Self {
+        DebugOverlay {
+            visible,
+            ..DebugOverlay::default()
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    // Reads the live keys rather than the game's, so toggling works during a
+    // replay and never ends up in a recording.
+    pub fn handle_keys(&mut self, keystate: &KeyState) {
+        let pressed = keystate.is_pressed(DEBUG_KEY);
+        if pressed && !self.key_held {
+            self.visible = !self.visible;
+        }
+        self.key_held = pressed;
+    }
+
+    pub fn record_frame(&mut self, update_ms: f64, draw_ms: f64) {
+        self.update_ms += (update_ms - self.update_ms) * SMOOTHING;
+        self.draw_ms += (draw_ms - self.draw_ms) * SMOOTHING;
+    }
+
+    pub fn update_ms(&self) -> f64 {
+        self.update_ms
+    }
+
+    pub fn draw_ms(&self) -> f64 {
+        self.draw_ms
+    }
+
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        renderer.draw_text(
+            &format!(
+                "update {:.2} ms  draw {:.2} ms",
+                self.update_ms, self.draw_ms
+            ),
+            &TIMINGS_POSITION,
+            &TIMINGS_STYLE,
+        )
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+/// How hard the walk is from `distance` metres on. Speed and the gap between
+/// segments ease towards the next stage; harder segments unlock all at once.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DifficultyStage {
+    pub distance: u32,
+    /// A multiple of the boy's running speed.
+    pub speed: f32,
+    pub obstacle_buffer: i32,
+    /// Segments up to this difficulty can be picked.
+    pub segment_difficulty: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Difficulty {
+    pub speed: f32,
+    pub obstacle_buffer: i32,
+    pub segment_difficulty: u32,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct DifficultyCurve {
+    stages: Vec<DifficultyStage>,
+}
+
+// The pace the game had before it got harder over time.
+impl Default for DifficultyCurve {
+    fn default() -> Self {
+        DifficultyCurve {
+            stages: vec![DifficultyStage {
+                distance: 0,
+                speed: 1.0,
+                obstacle_buffer: 20,
+                segment_difficulty: 0,
+            }],
+        }
+    }
+}
+
+impl DifficultyCurve {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let curve: DifficultyCurve = serde_json::from_str(json)
+            .map_err(|err| anyhow!("Could not parse difficulty curve {:#?}", err))?;
+        curve.validate()?;
+        Ok(curve)
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self.stages.first() {
+            None => return Err(anyhow!("Invalid difficulty curve: there are no stages")),
+            Some(first) if first.distance != 0 => {
+                return Err(anyhow!(
+                    "Invalid difficulty curve: the first stage must start at 0 m"
+                ))
+            }
+            Some(_first) => {}
+        }
+        if self
+            .stages
+            .windows(2)
+            .any(|stages| stages[1].distance <= stages[0].distance)
+        {
+            return Err(anyhow!(
+                "Invalid difficulty curve: stages must be in order of distance"
+            ));
+        }
+        if let Some(stage) = self.stages.iter().find(|stage| {
+            !stage.speed.is_finite() || stage.speed <= 0.0 || stage.obstacle_buffer < 0
+        }) {
+            return Err(anyhow!(
+                "Invalid difficulty curve: the stage at {} m needs a positive speed and a non-negative gap",
+                stage.distance
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn at(&self, distance: u32) -> Difficulty {
+        let next = self
+            .stages
+            .iter()
+            .position(|stage| stage.distance > distance)
+            .unwrap_or(self.stages.len());
+        let current = &self.stages[next.saturating_sub(1)];
+        match self.stages.get(next) {
+            Some(upcoming) if next > 0 => {
+                let progress = (distance - current.distance) as f32
+                    / (upcoming.distance - current.distance) as f32;
+                let ease = |from: f32, to: f32| from + (to - from) * progress;
+                Difficulty {
+                    speed: ease(current.speed, upcoming.speed),
+                    obstacle_buffer: ease(
+                        current.obstacle_buffer as f32,
+                        upcoming.obstacle_buffer as f32,
+                    )
+                    .round() as i32,
+                    segment_difficulty: current.segment_difficulty,
+                }
+            }
+            _ => Difficulty {
+                speed: current.speed,
+                obstacle_buffer: current.obstacle_buffer,
+                segment_difficulty: current.segment_difficulty,
+            },
+        }
+    }
+}
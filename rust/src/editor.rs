@@ -0,0 +1,491 @@
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+
+use crate::{
+    assets::LoadProgress,
+    browser,
+    engine::{
+        self, Camera, Game, ImageHandle, KeyState, Point, Rect, Renderer, SpriteSheet, TextAlign,
+        TextStyle,
+    },
+    game::Walk,
+    high_scores::escape_html,
+    input::{Action, KeyBindings},
+    obstacles::{Obstacle, FLOATING_PLATFORM_BOUNDING_BOXES, FLOATING_PLATFORM_SPRITES},
+    segments::{BoundingBox, ObstacleDefinition, SegmentDefinition, SegmentFile, SegmentPool},
+    store::LocalStore,
+    touch::Pointer,
+};
+
+const EDITOR_PARAMETER: &str = "editor";
+const BARRIER_IMAGE: &str = "stone";
+const DEFAULT_NAME: &str = "custom";
+const SNAP: i32 = 5;
+const GRID_SPACING: i32 = 50;
+const SCROLL_SPEED: i32 = 10;
+// About a second for the knock out to play before going back to editing.
+const KNOCKED_OUT_TICKS: u32 = 60;
+
+const CANVAS: Rect = Rect::new_from_x_y(0, 0, 600, 570);
+const SKY_COLOR: &str = "#7fb8e0";
+const GRID_COLOR: &str = "rgba(255, 255, 255, 0.3)";
+const START_COLOR: &str = "rgba(255, 255, 255, 0.8)";
+const BOX_COLOR: &str = "rgba(255, 0, 0, 0.4)";
+const GHOST_COLOR: &str = "rgba(255, 255, 255, 0.5)";
+const STATUS_STYLE: TextStyle = TextStyle {
+    font: "18px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Left,
+};
+
+pub fn requested() -> bool {
+    browser::query_parameter(EDITOR_PARAMETER).is_some()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tool {
+    Barrier,
+    Platform,
+}
+
+impl Tool {
+    fn label(&self) -> &'static str {
+        match self {
+            Tool::Barrier => "Barrier",
+            Tool::Platform => "Platform",
+        }
+    }
+}
+
+struct Toolbar {
+    barrier: UnboundedReceiver<()>,
+    platform: UnboundedReceiver<()>,
+    play: UnboundedReceiver<()>,
+    export: UnboundedReceiver<()>,
+}
+
+impl Toolbar {
+    fn show(name: &str, exported: Option<&str>) -> Self {
+        browser::draw_ui(&toolbar_ui(name, exported))
+            .and_then(|_unit| {
+                Ok(Toolbar {
+                    barrier: engine::click_events("editor_barrier")?,
+                    platform: engine::click_events("editor_platform")?,
+                    play: engine::click_events("editor_play")?,
+                    export: engine::click_events("editor_export")?,
+                })
+            })
+            .unwrap_or_else(|err| {
+                log!("Could not show the editor toolbar {:#?}", err);
+                Toolbar {
+                    barrier: unbounded().1,
+                    platform: unbounded().1,
+                    play: unbounded().1,
+                    export: unbounded().1,
+                }
+            })
+    }
+}
+
+fn pressed(receiver: &mut UnboundedReceiver<()>) -> bool {
+    matches!(receiver.try_next(), Ok(Some(())))
+}
+
+fn toolbar_ui(name: &str, exported: Option<&str>) -> String {
+    let exported = exported
+        .map(|json| {
+            format!(
+                "<textarea id='segment_json' readonly>{}</textarea>",
+                escape_html(json)
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "<div id='editor'><p>{}{}{}<input id='segment_name' value='{}'>{}</p>{}</div>",
+        "<button id='editor_barrier'>Barrier</button>",
+        "<button id='editor_platform'>Platform</button>",
+        "<button id='editor_play'>Play</button>",
+        escape_html(name),
+        "<button id='editor_export'>Export</button>",
+        exported
+    )
+}
+
+enum Mode {
+    Editing(Toolbar),
+    Playing { knocked_out_for: u32 },
+}
+
+/// Places barriers and platforms on a scrolling grid to make a segment, plays
+/// it on a real walk and exports it in the format of `segments.json`. Clicking
+/// an empty spot places the current tool there and clicking a piece removes it.
+pub struct Editor {
+    walk: Option<Walk>,
+    stone: ImageHandle,
+    sheet: Rc<SpriteSheet>,
+    bindings: KeyBindings,
+    name: String,
+    pieces: Vec<ObstacleDefinition>,
+    obstacles: Vec<Box<dyn Obstacle>>,
+    tool: Tool,
+    // Only ever moves sideways, and never left of where the segment starts.
+    camera: Camera,
+    cursor: Option<Point>,
+    mode: Mode,
+    last_keystate: KeyState,
+}
+
+impl Editor {
+    pub fn new(walk: Walk, stone: ImageHandle, sheet: Rc<SpriteSheet>) -> Self {
+        Editor {
+            walk: Some(walk),
+            stone,
+            sheet,
+            bindings: KeyBindings::load(&LocalStore),
+            name: DEFAULT_NAME.to_string(),
+            pieces: vec![],
+            obstacles: vec![],
+            tool: Tool::Barrier,
+            camera: Camera::new(),
+            cursor: None,
+            mode: Mode::Editing(Toolbar::show(DEFAULT_NAME, None)),
+            last_keystate: KeyState::new(),
+        }
+    }
+
+    pub fn pieces(&self) -> &[ObstacleDefinition] {
+        &self.pieces
+    }
+
+    pub fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, Mode::Playing { .. })
+    }
+
+    pub fn walk(&self) -> Option<&Walk> {
+        self.walk.as_ref()
+    }
+
+    pub fn select(&mut self, tool: Tool) {
+        self.tool = tool;
+    }
+
+    /// Removes the piece under `position`, or places the current tool there if
+    /// there isn't one. `position` is in canvas pixels.
+    pub fn click(&mut self, position: Point) {
+        let world = self.to_world(position);
+        match self.piece_at(&world) {
+            Some(index) => {
+                self.pieces.remove(index);
+            }
+            None => self.pieces.push(self.new_piece(world)),
+        }
+        self.rebuild();
+    }
+
+    pub fn export(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.segment())
+            .map_err(|err| anyhow!("Could not serialize segment {:#?}", err))
+    }
+
+    /// Runs the segment through the normal walk until the pause key is pressed
+    /// or the boy has been knocked out for a moment.
+    pub fn play(&mut self) -> Result<()> {
+        let pool = Rc::new(self.pool()?);
+        if let Err(err) = browser::hide_ui() {
+            log!("Error hiding the editor toolbar {:#?}", err);
+        }
+        self.walk = self.walk.take().map(|walk| {
+            let mut walk = Walk::with_segments(walk, pool);
+            walk.boy_mut().run_right();
+            walk
+        });
+        self.mode = Mode::Playing { knocked_out_for: 0 };
+        Ok(())
+    }
+
+    fn edit(&mut self) {
+        self.mode = Mode::Editing(Toolbar::show(&self.name, None));
+        self.rebuild();
+    }
+
+    fn segment(&self) -> SegmentDefinition {
+        SegmentDefinition {
+            name: self.name.clone(),
+            weight: 1,
+            difficulty: 0,
+            obstacles: self.pieces.clone(),
+        }
+    }
+
+    // Keeps what was typed in the toolbar, which goes away while playing.
+    fn read_name(&mut self) {
+        if let Some(name) = browser::input_value("segment_name")
+            .ok()
+            .filter(|name| !name.trim().is_empty())
+        {
+            self.name = name.trim().to_string();
+        }
+    }
+
+    fn pool(&self) -> Result<SegmentPool> {
+        SegmentPool::new(
+            SegmentFile {
+                segments: vec![self.segment()],
+            },
+            |key| match key {
+                BARRIER_IMAGE => Ok(self.stone.clone()),
+                _ => Err(anyhow!("Asset {} is not loaded", key)),
+            },
+            self.sheet.clone(),
+        )
+    }
+
+    // The obstacles are only drawn here, so they're made again whenever the
+    // pieces change.
+    fn rebuild(&mut self) {
+        self.obstacles = if self.pieces.is_empty() {
+            vec![]
+        } else {
+            self.pool()
+                .map(|pool| pool.create(0, 0))
+                .unwrap_or_else(|err| {
+                    log!("Could not build the segment {:#}", err);
+                    vec![]
+                })
+        };
+    }
+
+    fn to_world(&self, position: Point) -> Point {
+        let world = self.camera.to_world(&position);
+        Point {
+            x: snap(world.x).max(0),
+            y: snap(world.y),
+        }
+    }
+
+    fn new_piece(&self, position: Point) -> ObstacleDefinition {
+        match self.tool {
+            Tool::Barrier => ObstacleDefinition::Barrier {
+                image: BARRIER_IMAGE.to_string(),
+                x: position.x,
+                y: position.y,
+            },
+            Tool::Platform => ObstacleDefinition::Platform {
+                x: position.x,
+                y: position.y,
+                sprites: FLOATING_PLATFORM_SPRITES
+                    .iter()
+                    .map(|sprite| sprite.to_string())
+                    .collect(),
+                bounding_boxes: FLOATING_PLATFORM_BOUNDING_BOXES
+                    .iter()
+                    .map(BoundingBox::from)
+                    .collect(),
+            },
+        }
+    }
+
+    fn piece_at(&self, position: &Point) -> Option<usize> {
+        self.pieces.iter().position(|piece| {
+            self.bounding_boxes(piece)
+                .iter()
+                .any(|bounding_box| bounding_box.contains(position))
+        })
+    }
+
+    fn bounding_boxes(&self, piece: &ObstacleDefinition) -> Vec<Rect> {
+        match piece {
+            ObstacleDefinition::Barrier { x, y, .. } => vec![Rect::new_from_x_y(
+                *x,
+                *y,
+                self.stone.width(),
+                self.stone.height(),
+            )],
+            ObstacleDefinition::Platform {
+                x,
+                y,
+                bounding_boxes,
+                ..
+            } => bounding_boxes
+                .iter()
+                .map(|bounding_box| {
+                    Rect::new_from_x_y(
+                        x + bounding_box.x,
+                        y + bounding_box.y,
+                        bounding_box.width,
+                        bounding_box.height,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn update_editing(&mut self, keystate: &KeyState) {
+        let scroll = if keystate.is_pressed("ArrowLeft") {
+            -SCROLL_SPEED
+        } else if keystate.is_pressed("ArrowRight") {
+            SCROLL_SPEED
+        } else {
+            0
+        };
+        if scroll != 0 {
+            self.camera = Camera::at(Point {
+                x: (self.camera.x() + scroll).max(0),
+                y: 0,
+            });
+        }
+        let Mode::Editing(toolbar) = &mut self.mode else {
+            return;
+        };
+        if pressed(&mut toolbar.barrier) {
+            self.select(Tool::Barrier);
+        } else if pressed(&mut toolbar.platform) {
+            self.select(Tool::Platform);
+        } else if pressed(&mut toolbar.export) {
+            self.read_name();
+            match self.export() {
+                Ok(json) => {
+                    log!("{}", json);
+                    if let Err(err) = browser::hide_ui() {
+                        log!("Error hiding the editor toolbar {:#?}", err);
+                    }
+                    self.mode = Mode::Editing(Toolbar::show(&self.name, Some(&json)));
+                }
+                Err(err) => log!("Could not export the segment {:#}", err),
+            }
+        } else if pressed(&mut toolbar.play) {
+            self.read_name();
+            if let Err(err) = self.play() {
+                log!("Could not play the segment {:#}", err);
+            }
+        }
+    }
+
+    fn update_playing(&mut self, keystate: &KeyState) {
+        let actions = self.bindings.actions(keystate, &self.last_keystate);
+        let Some(walk) = &mut self.walk else {
+            return;
+        };
+        if actions.is_held(Action::Slide) {
+            walk.boy_mut().slide();
+        }
+        if actions.is_held(Action::Jump) {
+            walk.boy_mut().jump();
+        }
+        walk.update();
+        let knocked_out = walk.knocked_out();
+        if let Mode::Playing { knocked_out_for } = &mut self.mode {
+            if knocked_out {
+                *knocked_out_for += 1;
+            }
+            if actions.was_pressed(Action::Pause) || *knocked_out_for > KNOCKED_OUT_TICKS {
+                self.edit();
+            }
+        }
+    }
+
+    fn draw_grid(&self, renderer: &dyn Renderer) {
+        renderer.fill_rect(&CANVAS, SKY_COLOR);
+        let first_line = GRID_SPACING - self.camera.x().rem_euclid(GRID_SPACING);
+        (0..)
+            .map(|line| first_line + line * GRID_SPACING)
+            .take_while(|x| *x < CANVAS.width)
+            .for_each(|x| {
+                renderer.fill_rect(&Rect::new_from_x_y(x, 0, 1, CANVAS.height), GRID_COLOR)
+            });
+        (1..)
+            .map(|line| line * GRID_SPACING)
+            .take_while(|y| *y < CANVAS.height)
+            .for_each(|y| {
+                renderer.fill_rect(&Rect::new_from_x_y(0, y, CANVAS.width, 1), GRID_COLOR)
+            });
+        self.camera
+            .view(renderer)
+            .fill_rect(&Rect::new_from_x_y(0, 0, 2, CANVAS.height), START_COLOR);
+    }
+
+    fn draw_boxes(&self, renderer: &dyn Renderer, piece: &ObstacleDefinition, color: &str) {
+        let world = self.camera.view(renderer);
+        self.bounding_boxes(piece)
+            .iter()
+            .for_each(|bounding_box| world.fill_rect(bounding_box, color));
+    }
+
+    fn draw_editing(&self, renderer: &dyn Renderer) -> Result<()> {
+        self.draw_grid(renderer);
+        let world = self.camera.view(renderer);
+        self.obstacles
+            .iter()
+            .try_for_each(|obstacle| obstacle.draw(&world))?;
+        self.pieces
+            .iter()
+            .for_each(|piece| self.draw_boxes(renderer, piece, BOX_COLOR));
+        let status = match self.cursor {
+            Some(cursor) => {
+                let world = self.to_world(cursor);
+                if self.piece_at(&world).is_none() {
+                    self.draw_boxes(renderer, &self.new_piece(world), GHOST_COLOR);
+                }
+                format!("{}  {}, {}", self.tool.label(), world.x, world.y)
+            }
+            None => self.tool.label().to_string(),
+        };
+        renderer.draw_text(&status, &Point { x: 16, y: 554 }, &STATUS_STYLE)
+    }
+}
+
+fn snap(value: i32) -> i32 {
+    value.div_euclid(SNAP) * SNAP
+}
+
+#[async_trait(?Send)]
+impl Game for Editor {
+    async fn initialize(&self, _progress: &LoadProgress) -> Result<Box<dyn Game>> {
+        Err(anyhow!("Error: Editor is already initialized!"))
+    }
+
+    fn update(&mut self, keystate: &KeyState) -> Result<()> {
+        match self.mode {
+            Mode::Editing(_) => self.update_editing(keystate),
+            Mode::Playing { .. } => self.update_playing(keystate),
+        }
+        self.last_keystate = keystate.clone();
+        Ok(())
+    }
+
+    fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        renderer.clear(&CANVAS);
+        match (&self.mode, &self.walk) {
+            (Mode::Playing { .. }, Some(walk)) => walk.draw(renderer),
+            _ => self.draw_editing(renderer),
+        }
+    }
+
+    fn draw_debug(&self, renderer: &dyn Renderer) -> Result<()> {
+        match (&self.mode, &self.walk) {
+            (Mode::Playing { .. }, Some(walk)) => walk.draw_debug(renderer),
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_pointer(&mut self, pointer: Pointer) {
+        if self.is_playing() {
+            return;
+        }
+        match pointer {
+            Pointer::Down { position, .. } => {
+                self.cursor = Some(position);
+                self.click(position);
+            }
+            Pointer::Move { position, .. } => self.cursor = Some(position),
+            Pointer::Up { .. } | Pointer::Cancel { .. } => {}
+        }
+    }
+}
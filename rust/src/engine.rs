@@ -5,36 +5,50 @@ use futures::channel::mpsc::UnboundedReceiver;
 use futures::channel::oneshot::channel;
 use serde::Deserialize;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::AudioBuffer;
 use web_sys::AudioContext;
+use web_sys::AudioContextState;
 use web_sys::HtmlElement;
+use web_sys::{AudioBufferSourceNode, GainNode};
 use web_sys::{CanvasRenderingContext2d, HtmlImageElement};
 
+use crate::animation::{AnimationClip, ClipSettings};
+use crate::aseprite;
+use crate::assets::LoadProgress;
+use crate::benchmark::Benchmark;
 use crate::browser;
+use crate::debug::{self, DebugOverlay};
+use crate::error_scene::ErrorScene;
+use crate::gamepad::{self, GamepadSnapshot};
+use crate::loading::Loader;
+use crate::mixer::{Bus, MixerSettings};
+use crate::replay::InputMode;
 use crate::sound::create_audio_context;
 use crate::sound::decode_audio_data;
 use crate::sound::play_sound;
-use crate::sound::LOOPING;
+use crate::sound::Looping;
+use crate::sound::{create_gain, fade_out, set_gain, stop_sound};
+use crate::touch::{self, GestureRecognizer, Pointer, PointerKind};
 
-pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
+pub async fn load_image(source: &str) -> Result<ImageHandle> {
     let image = browser::new_image()?;
     let (complete_tx, complete_rx) = channel::<Result<()>>();
     let success_tx = Rc::new(Mutex::new(Some(complete_tx)));
     let error_tx = Rc::clone(&success_tx);
     let success_callback = browser::closure_once(move || {
         if let Some(success_tx) = success_tx.lock().ok().and_then(|mut opt| opt.take()) {
-            success_tx.send(Ok(()));
+            let _ = success_tx.send(Ok(()));
         }
     });
 
     let error_callback: Closure<dyn FnMut(JsValue)> = browser::closure_once(move |err| {
         if let Some(error_tx) = error_tx.lock().ok().and_then(|mut opt| opt.take()) {
-            error_tx.send(Err(anyhow!("Error Loading Image: {:#?}", err)));
+            let _ = error_tx.send(Err(anyhow!("Error Loading Image: {:#?}", err)));
         }
     });
     image.set_onload(Some(success_callback.as_ref().unchecked_ref()));
@@ -42,44 +56,236 @@ pub async fn load_image(source: &str) -> Result<HtmlImageElement> {
     image.set_src(source);
     complete_rx.await??;
 
-    Ok(image)
+    Ok(ImageHandle::new(source, image))
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Clone)]
+enum ImageSource {
+    Element(HtmlImageElement),
+    Headless,
+}
+
+#[derive(Clone)]
+pub struct ImageHandle {
+    name: Rc<str>,
+    width: i32,
+    height: i32,
+    source: ImageSource,
+}
+
+impl ImageHandle {
+    pub fn new(name: &str, element: HtmlImageElement) -> Self {
+        ImageHandle {
+            name: name.into(),
+            width: element.width() as i32,
+            height: element.height() as i32,
+            source: ImageSource::Element(element),
+        }
+    }
+
+    /// An image with dimensions but no pixels, for renderers that never touch a canvas.
+    pub fn headless(name: &str, width: i32, height: i32) -> Self {
+        ImageHandle {
+            name: name.into(),
+            width,
+            height,
+            source: ImageSource::Headless,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    fn element(&self) -> Option<&HtmlImageElement> {
+        match &self.source {
+            ImageSource::Element(element) => Some(element),
+            ImageSource::Headless => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
 pub struct SheetRect {
-    pub x: i16,
-    pub y: i16,
-    pub w: i16,
-    pub h: i16,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SheetSize {
+    pub w: i32,
+    pub h: i32,
+}
+
+/// A frame as TexturePacker writes it. `frame` is where the pixels sit in the
+/// atlas, with `w` and `h` as the sprite is shown; a rotated frame is stored a
+/// quarter turn clockwise, so it covers `h` by `w` of the atlas. A trimmed
+/// frame lost its transparent border, and `sprite_source_size` places what is
+/// left within the untrimmed `source_size`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Cell {
     pub frame: SheetRect,
+    #[serde(default)]
+    pub rotated: bool,
+    #[serde(default)]
+    pub trimmed: bool,
     pub sprite_source_size: SheetRect,
+    #[serde(default)]
+    source_size: Option<SheetSize>,
+    /// Named areas of the untrimmed sprite, like Aseprite's slices.
+    #[serde(skip)]
+    pub slices: HashMap<String, SheetRect>,
+}
+
+impl Cell {
+    /// The untrimmed size, which is the frame's own when the sheet leaves it out.
+    pub fn source_size(&self) -> SheetSize {
+        self.source_size.unwrap_or(SheetSize {
+            w: self.frame.w,
+            h: self.frame.h,
+        })
+    }
+
+    /// The part of the atlas the frame covers.
+    pub fn atlas_rect(&self) -> Rect {
+        if self.rotated {
+            Rect::new_from_x_y(self.frame.x, self.frame.y, self.frame.h, self.frame.w)
+        } else {
+            Rect::new_from_x_y(self.frame.x, self.frame.y, self.frame.w, self.frame.h)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NamedCell {
+    filename: String,
+    #[serde(flatten)]
+    cell: Cell,
+}
+
+// TexturePacker's "JSON (Hash)" keys frames by name, "JSON (Array)" lists them
+// with a `filename` each.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Frames {
+    Hash(HashMap<String, Cell>),
+    Array(Vec<NamedCell>),
+}
+
+fn deserialize_frames<'de, D>(
+    deserializer: D,
+) -> std::result::Result<HashMap<String, Cell>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match Frames::deserialize(deserializer)? {
+        Frames::Hash(frames) => frames,
+        Frames::Array(frames) => frames
+            .into_iter()
+            .map(|named| (named.filename, named.cell))
+            .collect(),
+    })
+}
+
+// TexturePacker writes the scale as a string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Scale {
+    Number(f32),
+    Text(String),
+}
+
+fn deserialize_scale<'de, D>(deserializer: D) -> std::result::Result<f32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let scale = match Scale::deserialize(deserializer)? {
+        Scale::Number(scale) => scale,
+        Scale::Text(text) => text.trim().parse().map_err(serde::de::Error::custom)?,
+    };
+    if scale > 0.0 {
+        Ok(scale)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "scale must be above zero, not {}",
+            scale
+        )))
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SheetMeta {
+    /// The atlas image the sheet was packed into.
+    pub image: Option<String>,
+    pub size: Option<SheetSize>,
+    /// How much larger than the game's pixels the atlas was packed, so a
+    /// sheet packed at 2 draws at half its atlas size.
+    #[serde(deserialize_with = "deserialize_scale")]
+    pub scale: f32,
+    pub animations: HashMap<String, ClipSettings>,
 }
 
-#[derive(Deserialize, Clone)]
+impl Default for SheetMeta {
+    fn default() -> Self {
+        SheetMeta {
+            image: None,
+            size: None,
+            scale: 1.0,
+            animations: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Default)]
 pub struct Sheet {
+    #[serde(deserialize_with = "deserialize_frames")]
     pub frames: HashMap<String, Cell>,
+    #[serde(default)]
+    pub meta: SheetMeta,
+    /// Clips the sheet names itself rather than leaving to its frame names.
+    #[serde(skip)]
+    pub clips: HashMap<String, AnimationClip>,
+}
+
+impl Sheet {
+    /// Reads a TexturePacker sheet, or an Aseprite export.
+    pub fn from_json(json: &str) -> Result<Self> {
+        if aseprite::is_export(json) {
+            aseprite::sheet(json)
+        } else {
+            serde_json::from_str(json)
+                .map_err(|err| anyhow!("Could not parse sprite sheet {:#?}", err))
+        }
+    }
 }
 
 pub struct Image {
-    element: HtmlImageElement,
+    element: ImageHandle,
     bounding_box: Rect,
 }
 
 impl Image {
-    pub fn new(element: HtmlImageElement, position: Point) -> Self {
-        let bounding_box = Rect::new(position, element.width() as i16, element.height() as i16);
+    pub fn new(element: ImageHandle, position: Point) -> Self {
+        let bounding_box = Rect::new(position, element.width(), element.height());
         Self {
             element,
             bounding_box,
         }
     }
 
-    pub fn draw(&self, renderer: &Renderer) {
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
         renderer.draw_entire_image(&self.element, &self.bounding_box.position)
     }
 
@@ -87,45 +293,58 @@ impl Image {
         &self.bounding_box
     }
 
-    pub fn move_horizontally(&mut self, distance: i16) {
-        self.bounding_box.set_x(self.bounding_box.x() + distance);
+    pub fn right(&self) -> i32 {
+        self.bounding_box.right()
     }
+}
 
-    pub fn set_x(&mut self, x: i16) {
-        self.bounding_box.set_x(x);
-    }
+/// Pressed for the single tick after the page is hidden or loses focus, so the
+/// pause goes through the recorded input like any other key.
+pub const FOCUS_LOST: &str = "FocusLost";
 
-    pub fn right(&self) -> i16 {
-        self.bounding_box.right()
-    }
+/// Buttons in the HTML UI press `Click:<id>` for a single tick, for the same reason.
+pub const CLICK_PREFIX: &str = "Click:";
+
+pub fn click_code(id: &str) -> String {
+    format!("{}{}", CLICK_PREFIX, id)
+}
+
+thread_local! {
+    static CLICKED_BUTTONS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
 }
 
 enum KeyPress {
     KeyUp(web_sys::KeyboardEvent),
     KeyDown(web_sys::KeyboardEvent),
+    FocusLost,
 }
 
+#[derive(Clone, Default)]
 pub struct KeyState {
-    pressed_keys: HashMap<String, web_sys::KeyboardEvent>,
+    pressed_keys: HashSet<String>,
 }
 
 impl KeyState {
-    fn new() -> Self {
+    pub fn new() -> Self {
         KeyState {
-            pressed_keys: HashMap::new(),
+            pressed_keys: HashSet::new(),
         }
     }
 
     pub fn is_pressed(&self, code: &str) -> bool {
-        self.pressed_keys.contains_key(code)
+        self.pressed_keys.contains(code)
+    }
+
+    pub fn pressed_keys(&self) -> impl Iterator<Item = &str> {
+        self.pressed_keys.iter().map(String::as_str)
     }
 
-    fn set_pressed(&mut self, code: &str, event: web_sys::KeyboardEvent) {
-        self.pressed_keys.insert(code.into(), event);
+    pub fn set_pressed(&mut self, code: &str) {
+        self.pressed_keys.insert(code.into());
     }
 
-    fn set_released(&mut self, code: &str) {
-        self.pressed_keys.remove(code.into());
+    pub fn set_released(&mut self, code: &str) {
+        self.pressed_keys.remove(code);
     }
 }
 
@@ -133,24 +352,132 @@ fn prepare_input() -> Result<UnboundedReceiver<KeyPress>> {
     let (keydown_sender, keyevent_receiver) = unbounded();
     let keydown_sender = Rc::new(RefCell::new(keydown_sender));
     let keyup_sender = Rc::clone(&keydown_sender);
+    let blur_sender = Rc::clone(&keydown_sender);
+    let visibility_sender = Rc::clone(&keydown_sender);
     let onkeydown = browser::closure_wrap(Box::new(move |keycode: web_sys::KeyboardEvent| {
-        keydown_sender
+        let _ = keydown_sender
             .borrow_mut()
             .start_send(KeyPress::KeyDown(keycode));
     }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
     let onkeyup = browser::closure_wrap(Box::new(move |keycode: web_sys::KeyboardEvent| {
-        keyup_sender
+        let _ = keyup_sender
             .borrow_mut()
             .start_send(KeyPress::KeyUp(keycode));
     }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
+    let onblur = browser::closure_wrap(Box::new(move || {
+        let _ = blur_sender.borrow_mut().start_send(KeyPress::FocusLost);
+    }) as Box<dyn FnMut()>);
+    let onvisibilitychange = browser::closure_wrap(Box::new(move || {
+        if browser::document().is_ok_and(|document| document.hidden()) {
+            let _ = visibility_sender
+                .borrow_mut()
+                .start_send(KeyPress::FocusLost);
+        }
+    }) as Box<dyn FnMut()>);
 
     browser::window()?.set_onkeydown(Some(onkeydown.as_ref().unchecked_ref()));
     browser::window()?.set_onkeyup(Some(onkeyup.as_ref().unchecked_ref()));
+    browser::window()?.set_onblur(Some(onblur.as_ref().unchecked_ref()));
+    browser::document()?.set_onvisibilitychange(Some(onvisibilitychange.as_ref().unchecked_ref()));
     onkeydown.forget();
     onkeyup.forget();
+    onblur.forget();
+    onvisibilitychange.forget();
     Ok(keyevent_receiver)
 }
 
+fn prepare_pointer_input() -> Result<UnboundedReceiver<Pointer>> {
+    let (pointer_sender, pointer_receiver) = unbounded();
+    let pointer_sender = Rc::new(RefCell::new(pointer_sender));
+    let canvas = browser::canvas()?;
+    let listen = |event_name: &str,
+                  to_pointer: fn(&web_sys::PointerEvent, Point) -> Pointer|
+     -> Result<()> {
+        let sender = Rc::clone(&pointer_sender);
+        let target = canvas.clone();
+        let listener = browser::closure_wrap(Box::new(move |event: web_sys::PointerEvent| {
+            event.prevent_default();
+            let position = canvas_position(&target, &event);
+            let _ = sender.borrow_mut().start_send(to_pointer(&event, position));
+        }) as Box<dyn FnMut(web_sys::PointerEvent)>);
+        canvas
+            .add_event_listener_with_callback(event_name, listener.as_ref().unchecked_ref())
+            .map_err(|err| anyhow!("Could not listen for {} {:#?}", event_name, err))?;
+        listener.forget();
+        Ok(())
+    };
+    listen("pointerdown", |event, position| Pointer::Down {
+        id: event.pointer_id(),
+        kind: match event.pointer_type().as_str() {
+            "touch" => PointerKind::Touch,
+            "pen" => PointerKind::Pen,
+            _ => PointerKind::Mouse,
+        },
+        position,
+        time: event.time_stamp(),
+    })?;
+    listen("pointermove", |event, position| Pointer::Move {
+        id: event.pointer_id(),
+        position,
+        time: event.time_stamp(),
+    })?;
+    listen("pointerup", |event, position| Pointer::Up {
+        id: event.pointer_id(),
+        position,
+        time: event.time_stamp(),
+    })?;
+    listen("pointercancel", |event, _position| Pointer::Cancel {
+        id: event.pointer_id(),
+    })?;
+    Ok(pointer_receiver)
+}
+
+// The canvas may be scaled by CSS on small screens, so map back to canvas pixels.
+fn canvas_position(canvas: &web_sys::HtmlCanvasElement, event: &web_sys::PointerEvent) -> Point {
+    let scale_x = canvas.width() as f64 / canvas.client_width().max(1) as f64;
+    let scale_y = canvas.height() as f64 / canvas.client_height().max(1) as f64;
+    Point {
+        x: (event.offset_x() as f64 * scale_x) as i32,
+        y: (event.offset_y() as f64 * scale_y) as i32,
+    }
+}
+
+fn process_pointers(
+    gestures: &mut GestureRecognizer,
+    pointer_receiver: &mut UnboundedReceiver<Pointer>,
+    mut game: Option<&mut Box<dyn Game>>,
+) {
+    while let Ok(Some(pointer)) = pointer_receiver.try_next() {
+        gestures.handle(pointer);
+        if let Some(game) = &mut game {
+            game.handle_pointer(pointer);
+        }
+    }
+}
+
+/// Releases the codes that are only pressed for the tick after they happen.
+fn release_one_shot_keys(keystate: &mut KeyState) {
+    keystate.set_released(FOCUS_LOST);
+    touch::GESTURE_CODES
+        .iter()
+        .for_each(|code| keystate.set_released(code));
+    let clicks: Vec<String> = keystate
+        .pressed_keys()
+        .filter(|code| code.starts_with(CLICK_PREFIX))
+        .map(str::to_string)
+        .collect();
+    clicks.iter().for_each(|code| keystate.set_released(code));
+}
+
+fn process_clicks(keystate: &mut KeyState) {
+    CLICKED_BUTTONS.with(|clicked| {
+        clicked
+            .take()
+            .iter()
+            .for_each(|code| keystate.set_pressed(code))
+    });
+}
+
 fn process_input(state: &mut KeyState, keyevent_receiver: &mut UnboundedReceiver<KeyPress>) {
     loop {
         match keyevent_receiver.try_next() {
@@ -158,21 +485,22 @@ fn process_input(state: &mut KeyState, keyevent_receiver: &mut UnboundedReceiver
             Err(_err) => break,
             Ok(Some(evt)) => match evt {
                 KeyPress::KeyUp(evt) => state.set_released(&evt.code()),
-                KeyPress::KeyDown(evt) => state.set_pressed(&evt.code(), evt),
+                KeyPress::KeyDown(evt) => state.set_pressed(&evt.code()),
+                KeyPress::FocusLost => state.set_pressed(FOCUS_LOST),
             },
         }
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Rect {
     position: Point,
-    pub width: i16,
-    pub height: i16,
+    pub width: i32,
+    pub height: i32,
 }
 
 impl Rect {
-    pub const fn new(position: Point, width: i16, height: i16) -> Self {
+    pub const fn new(position: Point, width: i32, height: i32) -> Self {
         Rect {
             position,
             width,
@@ -180,15 +508,15 @@ impl Rect {
         }
     }
 
-    pub const fn new_from_x_y(x: i16, y: i16, width: i16, height: i16) -> Self {
+    pub const fn new_from_x_y(x: i32, y: i32, width: i32, height: i32) -> Self {
         Rect::new(Point { x, y }, width, height)
     }
 
-    pub fn x(&self) -> i16 {
+    pub fn x(&self) -> i32 {
         self.position.x
     }
 
-    pub fn y(&self) -> i16 {
+    pub fn y(&self) -> i32 {
         self.position.y
     }
 
@@ -199,36 +527,188 @@ impl Rect {
             && self.bottom() > rect.y()
     }
 
-    pub fn right(&self) -> i16 {
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.x()
+            && point.x < self.right()
+            && point.y >= self.y()
+            && point.y < self.bottom()
+    }
+
+    pub fn right(&self) -> i32 {
         self.x() + self.width
     }
 
-    pub fn bottom(&self) -> i16 {
+    pub fn bottom(&self) -> i32 {
         self.y() + self.height
     }
 
-    pub fn set_x(&mut self, x: i16) {
+    pub fn set_x(&mut self, x: i32) {
         self.position.x = x
     }
 
-    pub fn set_y(&mut self, y: i16) {
+    pub fn set_y(&mut self, y: i32) {
         self.position.y = y
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Point {
-    pub x: i16,
-    pub y: i16,
+    pub x: i32,
+    pub y: i32,
+}
+
+// The camera closes a quarter of the gap to where it should be each tick.
+const VERTICAL_EASING: i32 = 4;
+
+// How much of a shake is left, counted down a tick at a time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Shake {
+    magnitude: i32,
+    ticks: u16,
+    remaining: u16,
+}
+
+impl Shake {
+    // Jitters back and forth on both axes, dying away as it runs out. It
+    // follows the tick rather than a random number, so replays shake the same.
+    fn offset(&self) -> Point {
+        if self.remaining == 0 {
+            return Point::default();
+        }
+        let strength = self.magnitude * i32::from(self.remaining) / i32::from(self.ticks.max(1));
+        let sign = |odd: bool| if odd { -1 } else { 1 };
+        Point {
+            x: strength * sign(self.remaining % 2 == 1),
+            y: strength * sign(self.remaining / 2 % 2 == 1),
+        }
+    }
+}
+
+/// Which part of the world is on the canvas. Things in the world stay where
+/// they are and the camera moves past them, keeping whatever it follows at the
+/// same place on screen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Camera {
+    // The world point at the canvas's top left.
+    position: Point,
+    // Where the followed target sits on screen.
+    anchor: Point,
+    // With a margin, the camera rises when the target gets closer than this
+    // to the top of the canvas, and comes back down to the ground after.
+    top_margin: Option<i32>,
+    shake: Shake,
+}
+
+impl Camera {
+    /// A camera over the world's origin, so world and screen coordinates
+    /// start out the same.
+    pub fn new() -> Self {
+        Camera::default()
+    }
+
+    pub fn at(position: Point) -> Self {
+        Camera {
+            position,
+            ..Camera::default()
+        }
+    }
+
+    /// A camera that keeps `target` where it is on screen now.
+    pub fn following(target: Point) -> Self {
+        Camera {
+            anchor: target,
+            ..Camera::default()
+        }
+    }
+
+    pub fn follow_vertically(&mut self, top_margin: i32) {
+        self.top_margin = Some(top_margin);
+    }
+
+    /// Where the camera is, without any shake. Game logic should use this.
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn x(&self) -> i32 {
+        self.position.x
+    }
+
+    /// Where the camera is drawn from, shake included.
+    pub fn view_position(&self) -> Point {
+        let shake = self.shake.offset();
+        Point {
+            x: self.position.x + shake.x,
+            y: self.position.y + shake.y,
+        }
+    }
+
+    pub fn follow(&mut self, target: &Point) {
+        self.position.x = target.x - self.anchor.x;
+        if let Some(top_margin) = self.top_margin {
+            // Never below the ground, and easing rather than snapping.
+            let wanted = (target.y - top_margin).min(0);
+            let distance = wanted - self.position.y;
+            self.position.y += match distance / VERTICAL_EASING {
+                0 => distance.signum(),
+                step => step,
+            };
+        }
+    }
+
+    pub fn shake(&mut self, magnitude: i32, ticks: u16) {
+        self.shake = Shake {
+            magnitude,
+            ticks,
+            remaining: ticks,
+        };
+    }
+
+    pub fn is_shaking(&self) -> bool {
+        self.shake.remaining > 0
+    }
+
+    /// Runs the shake down. Called once per tick.
+    pub fn update(&mut self) {
+        self.shake.remaining = self.shake.remaining.saturating_sub(1);
+    }
+
+    pub fn to_screen(&self, point: &Point) -> Point {
+        let view = self.view_position();
+        Point {
+            x: point.x - view.x,
+            y: point.y - view.y,
+        }
+    }
+
+    pub fn to_world(&self, point: &Point) -> Point {
+        let view = self.view_position();
+        Point {
+            x: point.x + view.x,
+            y: point.y + view.y,
+        }
+    }
+
+    fn rect_to_screen(&self, rect: &Rect) -> Rect {
+        Rect::new(self.to_screen(&rect.position), rect.width, rect.height)
+    }
+
+    /// Draws world coordinates through this camera onto `renderer`.
+    pub fn view<'a>(&'a self, renderer: &'a dyn Renderer) -> CameraRenderer<'a> {
+        CameraRenderer {
+            camera: self,
+            renderer,
+        }
+    }
 }
 
 pub struct SpriteSheet {
     sheet: Sheet,
-    image: HtmlImageElement,
+    image: ImageHandle,
 }
 
 impl SpriteSheet {
-    pub fn new(sheet: Sheet, image: HtmlImageElement) -> Self {
+    pub fn new(sheet: Sheet, image: ImageHandle) -> Self {
         SpriteSheet { sheet, image }
     }
 
@@ -236,17 +716,104 @@ impl SpriteSheet {
         self.sheet.frames.get(name)
     }
 
-    pub fn draw(&self, renderer: &Renderer, source: &Rect, destination: &Rect) {
-        renderer.draw_image(&self.image, source, destination);
+    /// Where `cell` lands when its untrimmed top left is at `position`.
+    pub fn destination(&self, cell: &Cell, position: &Point) -> Rect {
+        Rect::new_from_x_y(
+            position.x + self.scaled(cell.sprite_source_size.x),
+            position.y + self.scaled(cell.sprite_source_size.y),
+            self.scaled(cell.frame.w),
+            self.scaled(cell.frame.h),
+        )
+    }
+
+    /// The untrimmed size of `cell` in the game's pixels.
+    pub fn source_size(&self, cell: &Cell) -> SheetSize {
+        let size = cell.source_size();
+        SheetSize {
+            w: self.scaled(size.w),
+            h: self.scaled(size.h),
+        }
+    }
+
+    /// The slice called `name` on `cell`, for a sprite drawn at `position`.
+    pub fn slice(&self, cell: &Cell, name: &str, position: &Point) -> Option<Rect> {
+        cell.slices.get(name).map(|slice| {
+            Rect::new_from_x_y(
+                position.x + self.scaled(slice.x),
+                position.y + self.scaled(slice.y),
+                self.scaled(slice.w),
+                self.scaled(slice.h),
+            )
+        })
+    }
+
+    pub fn draw(&self, renderer: &dyn Renderer, cell: &Cell, position: &Point) -> Result<()> {
+        let destination = self.destination(cell, position);
+        if cell.rotated {
+            renderer.draw_rotated_image(&self.image, &cell.atlas_rect(), &destination)
+        } else {
+            renderer.draw_image(&self.image, &cell.atlas_rect(), &destination)
+        }
+    }
+
+    fn scaled(&self, length: i32) -> i32 {
+        (length as f32 / self.sheet.meta.scale).round() as i32
     }
 }
 
-pub struct Renderer {
+pub trait Renderer {
+    fn clear(&self, rect: &Rect);
+    fn draw_image(&self, image: &ImageHandle, frame: &Rect, destination: &Rect) -> Result<()>;
+    /// Draws a frame stored a quarter turn clockwise in `image`, turned back
+    /// upright to fill `destination`.
+    fn draw_rotated_image(
+        &self,
+        image: &ImageHandle,
+        frame: &Rect,
+        destination: &Rect,
+    ) -> Result<()>;
+    fn draw_entire_image(&self, image: &ImageHandle, position: &Point) -> Result<()>;
+    fn draw_text(&self, text: &str, position: &Point, style: &TextStyle) -> Result<()>;
+    fn fill_rect(&self, rect: &Rect, color: &str);
+    fn stroke_rect(&self, rect: &Rect, color: &str);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    pub font: &'static str,
+    pub color: &'static str,
+    pub align: TextAlign,
+}
+
+pub struct CanvasRenderer {
     context: CanvasRenderingContext2d,
 }
 
-impl Renderer {
-    pub fn clear(&self, rect: &Rect) {
+impl CanvasRenderer {
+    pub fn new(context: CanvasRenderingContext2d) -> Self {
+        CanvasRenderer { context }
+    }
+}
+
+impl Renderer for CanvasRenderer {
+    fn clear(&self, rect: &Rect) {
         self.context.clear_rect(
             rect.x().into(),
             rect.y().into(),
@@ -255,70 +822,415 @@ impl Renderer {
         );
     }
 
-    pub fn draw_image(&self, image: &HtmlImageElement, frame: &Rect, destination: &Rect) {
-        self.context
-            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                &image,
-                frame.x().into(),
-                frame.y().into(),
-                frame.width.into(),
-                frame.height.into(),
+    fn draw_image(&self, image: &ImageHandle, frame: &Rect, destination: &Rect) -> Result<()> {
+        match image.element() {
+            Some(element) => self
+                .context
+                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    element,
+                    frame.x().into(),
+                    frame.y().into(),
+                    frame.width.into(),
+                    frame.height.into(),
+                    destination.x().into(),
+                    destination.y().into(),
+                    destination.width.into(),
+                    destination.height.into(),
+                )
+                .map_err(|err| anyhow!("Could not draw image {} {:#?}", image.name(), err)),
+            None => Ok(()),
+        }
+    }
+
+    fn draw_rotated_image(
+        &self,
+        image: &ImageHandle,
+        frame: &Rect,
+        destination: &Rect,
+    ) -> Result<()> {
+        let Some(element) = image.element() else {
+            return Ok(());
+        };
+        self.context.save();
+        let drawn = self
+            .context
+            .translate(
                 destination.x().into(),
-                destination.y().into(),
-                destination.width.into(),
-                destination.height.into(),
+                f64::from(destination.y()) + f64::from(destination.height),
             )
-            .expect("Drawing is throwing exceptions! Unrecoverable error");
+            .and_then(|_| self.context.rotate(-std::f64::consts::FRAC_PI_2))
+            .and_then(|_| {
+                self.context
+                    .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                        element,
+                        frame.x().into(),
+                        frame.y().into(),
+                        frame.width.into(),
+                        frame.height.into(),
+                        0.0,
+                        0.0,
+                        destination.height.into(),
+                        destination.width.into(),
+                    )
+            });
+        self.context.restore();
+        drawn.map_err(|err| anyhow!("Could not draw image {} {:#?}", image.name(), err))
     }
 
-    pub fn draw_entire_image(&self, image: &HtmlImageElement, position: &Point) {
+    fn draw_entire_image(&self, image: &ImageHandle, position: &Point) -> Result<()> {
+        match image.element() {
+            Some(element) => self
+                .context
+                .draw_image_with_html_image_element(element, position.x.into(), position.y.into())
+                .map_err(|err| anyhow!("Could not draw image {} {:#?}", image.name(), err)),
+            None => Ok(()),
+        }
+    }
+
+    fn draw_text(&self, text: &str, position: &Point, style: &TextStyle) -> Result<()> {
+        self.context.set_font(style.font);
+        self.context.set_fill_style_str(style.color);
+        self.context.set_text_align(style.align.as_str());
         self.context
-            .draw_image_with_html_image_element(image, position.x.into(), position.y.into())
-            .expect("Drawing is throwing exceptions! Unrecoverable error.");
+            .fill_text(text, position.x.into(), position.y.into())
+            .map_err(|err| anyhow!("Could not draw text {:#?}", err))
+    }
+
+    fn fill_rect(&self, rect: &Rect, color: &str) {
+        self.context.set_fill_style_str(color);
+        self.context.fill_rect(
+            rect.x().into(),
+            rect.y().into(),
+            rect.width.into(),
+            rect.height.into(),
+        );
+    }
+
+    fn stroke_rect(&self, rect: &Rect, color: &str) {
+        self.context.set_stroke_style_str(color);
+        self.context.set_line_width(1.0);
+        // Half a pixel in, so a one pixel line covers whole pixels.
+        self.context.stroke_rect(
+            f64::from(rect.x()) + 0.5,
+            f64::from(rect.y()) + 0.5,
+            f64::from(rect.width) - 1.0,
+            f64::from(rect.height) - 1.0,
+        );
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    Clear(Rect),
+    DrawImage {
+        image: String,
+        frame: Rect,
+        destination: Rect,
+    },
+    DrawRotatedImage {
+        image: String,
+        frame: Rect,
+        destination: Rect,
+    },
+    DrawEntireImage {
+        image: String,
+        position: Point,
+    },
+    DrawText {
+        text: String,
+        position: Point,
+        style: TextStyle,
+    },
+    FillRect {
+        rect: Rect,
+        color: String,
+    },
+    StrokeRect {
+        rect: Rect,
+        color: String,
+    },
+}
+
+/// Records every draw call instead of rasterising it, so the draw path can run natively.
+#[derive(Default)]
+pub struct HeadlessRenderer {
+    commands: RefCell<Vec<DrawCommand>>,
+}
+
+impl HeadlessRenderer {
+    pub fn new() -> Self {
+        HeadlessRenderer::default()
+    }
+
+    pub fn commands(&self) -> Vec<DrawCommand> {
+        self.commands.borrow().clone()
+    }
+
+    pub fn take_commands(&self) -> Vec<DrawCommand> {
+        self.commands.take()
+    }
+
+    fn record(&self, command: DrawCommand) {
+        self.commands.borrow_mut().push(command);
+    }
+}
+
+impl Renderer for HeadlessRenderer {
+    fn clear(&self, rect: &Rect) {
+        self.record(DrawCommand::Clear(rect.clone()));
+    }
+
+    fn draw_image(&self, image: &ImageHandle, frame: &Rect, destination: &Rect) -> Result<()> {
+        self.record(DrawCommand::DrawImage {
+            image: image.name().to_string(),
+            frame: frame.clone(),
+            destination: destination.clone(),
+        });
+        Ok(())
+    }
+
+    fn draw_rotated_image(
+        &self,
+        image: &ImageHandle,
+        frame: &Rect,
+        destination: &Rect,
+    ) -> Result<()> {
+        self.record(DrawCommand::DrawRotatedImage {
+            image: image.name().to_string(),
+            frame: frame.clone(),
+            destination: destination.clone(),
+        });
+        Ok(())
+    }
+
+    fn draw_entire_image(&self, image: &ImageHandle, position: &Point) -> Result<()> {
+        self.record(DrawCommand::DrawEntireImage {
+            image: image.name().to_string(),
+            position: *position,
+        });
+        Ok(())
+    }
+
+    fn draw_text(&self, text: &str, position: &Point, style: &TextStyle) -> Result<()> {
+        self.record(DrawCommand::DrawText {
+            text: text.to_string(),
+            position: *position,
+            style: *style,
+        });
+        Ok(())
+    }
+
+    fn fill_rect(&self, rect: &Rect, color: &str) {
+        self.record(DrawCommand::FillRect {
+            rect: rect.clone(),
+            color: color.to_string(),
+        });
+    }
+
+    fn stroke_rect(&self, rect: &Rect, color: &str) {
+        self.record(DrawCommand::StrokeRect {
+            rect: rect.clone(),
+            color: color.to_string(),
+        });
+    }
+}
+
+/// A renderer that takes world coordinates and draws them where the camera
+/// shows them.
+pub struct CameraRenderer<'a> {
+    camera: &'a Camera,
+    renderer: &'a dyn Renderer,
+}
+
+impl Renderer for CameraRenderer<'_> {
+    fn clear(&self, rect: &Rect) {
+        self.renderer.clear(&self.camera.rect_to_screen(rect));
+    }
+
+    fn draw_image(&self, image: &ImageHandle, frame: &Rect, destination: &Rect) -> Result<()> {
+        self.renderer
+            .draw_image(image, frame, &self.camera.rect_to_screen(destination))
+    }
+
+    fn draw_rotated_image(
+        &self,
+        image: &ImageHandle,
+        frame: &Rect,
+        destination: &Rect,
+    ) -> Result<()> {
+        self.renderer
+            .draw_rotated_image(image, frame, &self.camera.rect_to_screen(destination))
+    }
+
+    fn draw_entire_image(&self, image: &ImageHandle, position: &Point) -> Result<()> {
+        self.renderer
+            .draw_entire_image(image, &self.camera.to_screen(position))
+    }
+
+    fn draw_text(&self, text: &str, position: &Point, style: &TextStyle) -> Result<()> {
+        self.renderer
+            .draw_text(text, &self.camera.to_screen(position), style)
+    }
+
+    fn fill_rect(&self, rect: &Rect, color: &str) {
+        self.renderer
+            .fill_rect(&self.camera.rect_to_screen(rect), color);
+    }
+
+    fn stroke_rect(&self, rect: &Rect, color: &str) {
+        self.renderer
+            .stroke_rect(&self.camera.rect_to_screen(rect), color);
     }
 }
 
 #[async_trait(?Send)]
 pub trait Game {
-    async fn initialize(&self) -> Result<Box<dyn Game>>;
-    fn update(&mut self, keystate: &KeyState);
-    fn draw(&self, renderer: &Renderer);
+    async fn initialize(&self, progress: &LoadProgress) -> Result<Box<dyn Game>>;
+    fn update(&mut self, keystate: &KeyState) -> Result<()>;
+    fn draw(&self, renderer: &dyn Renderer) -> Result<()>;
+    /// Raw pointer events on the canvas, for scenes that need more than the
+    /// gestures and buttons the action layer gets.
+    fn handle_pointer(&mut self, _pointer: Pointer) {}
+    /// Drawn over the game while the debug overlay is shown.
+    fn draw_debug(&self, _renderer: &dyn Renderer) -> Result<()> {
+        Ok(())
+    }
 }
 
-const FRAME_SIZE: f32 = 1.0 / 60.0 * 1000.0;
+pub(crate) const FRAME_SIZE: f32 = 1.0 / 60.0 * 1000.0;
+// Anything beyond this after a stall (a hidden tab, a debugger pause) is dropped
+// rather than replayed as a burst of updates.
+const MAX_CATCH_UP_FRAMES: u32 = 3;
 
 pub struct GameLoop {
     last_frame: f64,
     accumulated_delta: f32,
+    benchmark: Option<Benchmark>,
 }
 
 type SharedLoopClosure = Rc<RefCell<Option<browser::LoopClosure>>>;
 
 impl GameLoop {
-    pub async fn start(game: impl Game + 'static) -> Result<()> {
+    pub fn new(now: f64) -> Self {
+        GameLoop {
+            last_frame: now,
+            accumulated_delta: 0.0,
+            benchmark: None,
+        }
+    }
+
+    /// Advances the clock to `now` and returns how many fixed updates are due.
+    pub fn advance(&mut self, now: f64) -> u32 {
+        let frame_time = (now - self.last_frame) as f32;
+        self.last_frame = now;
+        self.accumulated_delta += frame_time;
+        let mut updates = 0;
+        while self.accumulated_delta >= FRAME_SIZE {
+            self.accumulated_delta -= FRAME_SIZE;
+            updates += 1;
+        }
+        updates.min(MAX_CATCH_UP_FRAMES)
+    }
+
+    pub async fn start(game: impl Game + 'static, mut input: InputMode) -> Result<()> {
         let mut keyevent_receiver = prepare_input()?;
-        let mut game = game.initialize().await?;
+        let mut pointer_receiver = prepare_pointer_input()?;
+        let mut gestures = GestureRecognizer::new(touch::default_buttons());
+        let mut loader = Loader::start(Rc::new(game));
+        let mut running: Option<Box<dyn Game>> = None;
+        let mut error_scene: Option<ErrorScene> = None;
         let mut game_loop = GameLoop {
-            last_frame: browser::now()?,
-            accumulated_delta: 0.0,
-        };
-        let renderer = Renderer {
-            context: browser::context()?,
+            benchmark: Benchmark::for_input(&input),
+            ..GameLoop::new(browser::now()?)
         };
+        let renderer = CanvasRenderer::new(browser::context()?);
         let f: SharedLoopClosure = Rc::new(RefCell::new(None));
         let g = f.clone();
         let mut keystate = KeyState::new();
+        let mut debug_overlay = DebugOverlay::new(debug::requested());
         *g.borrow_mut() = Some(browser::create_raf_closure(move |perf: f64| {
             process_input(&mut keystate, &mut keyevent_receiver);
-            let frame_time = perf - game_loop.last_frame;
-            game_loop.accumulated_delta += frame_time as f32;
-            while game_loop.accumulated_delta > FRAME_SIZE {
-                game.update(&keystate);
-                game_loop.accumulated_delta -= FRAME_SIZE;
+            process_clicks(&mut keystate);
+            // Browsers without the Gamepad API just play with the keyboard.
+            if let Ok(gamepads) = browser::gamepads() {
+                let snapshots: Vec<GamepadSnapshot> =
+                    gamepads.iter().map(GamepadSnapshot::from).collect();
+                gamepad::merge(&mut keystate, &snapshots);
+            }
+            process_pointers(&mut gestures, &mut pointer_receiver, running.as_mut());
+            gestures.apply(&mut keystate);
+            debug_overlay.handle_keys(&keystate);
+            if running.is_none() {
+                running = loader.poll();
+                // Time spent loading must not count towards the first update.
+                game_loop.last_frame = perf;
+            }
+            if error_scene
+                .as_mut()
+                .is_some_and(|scene| scene.resumed(&keystate))
+            {
+                error_scene = None;
+                // The time spent on the error screen is skipped, not caught up on.
+                game_loop.last_frame = perf;
+            }
+            let frame = match (&mut running, &error_scene) {
+                (_, Some(scene)) => scene.draw(&renderer),
+                (None, None) => loader.draw(&renderer),
+                (Some(game), None) => (|| -> Result<()> {
+                    if let Some(benchmark) = &mut game_loop.benchmark {
+                        // One tick per frame, whatever the display rate, so every run does the same work.
+                        let started = browser::now().unwrap_or(perf);
+                        game.update(&input.next_keystate(&keystate))?;
+                        release_one_shot_keys(&mut keystate);
+                        gestures.updated();
+                        let updated = browser::now().unwrap_or(started);
+                        game.draw(&renderer)?;
+                        let drawn = browser::now().unwrap_or(updated);
+                        debug_overlay.record_frame(updated - started, drawn - updated);
+                        benchmark.record_frame(
+                            updated - started,
+                            drawn - updated,
+                            browser::memory_bytes().unwrap_or_default(),
+                        );
+                        if benchmark.is_complete() {
+                            benchmark.publish();
+                            game_loop.benchmark = None;
+                        }
+                        game_loop.last_frame = perf;
+                    } else {
+                        let started = browser::now().unwrap_or(perf);
+                        for _ in 0..game_loop.advance(perf) {
+                            game.update(&input.next_keystate(&keystate))?;
+                            release_one_shot_keys(&mut keystate);
+                            gestures.updated();
+                        }
+                        let updated = browser::now().unwrap_or(started);
+                        game.draw(&renderer)?;
+                        let drawn = browser::now().unwrap_or(updated);
+                        debug_overlay.record_frame(updated - started, drawn - updated);
+                    }
+                    if debug_overlay.is_visible() {
+                        game.draw_debug(&renderer)?;
+                        debug_overlay.draw(&renderer)?;
+                    }
+                    Ok(())
+                })(),
+            };
+            if let Err(err) = frame.and_then(|_unit| gestures.draw(&renderer)) {
+                if error_scene.is_none() {
+                    error_scene = Some(ErrorScene::show(&err));
+                } else {
+                    log!("Error drawing the error scene {:#}", err);
+                }
+            }
+            let next_frame = f
+                .borrow()
+                .as_ref()
+                .ok_or_else(|| anyhow!("GameLoop: Loop is None"))
+                .and_then(browser::request_animation_frame);
+            if let Err(err) = next_frame {
+                log!("Error requesting the next animation frame {:#?}", err);
             }
-            game_loop.last_frame = perf;
-            game.draw(&renderer);
-            browser::request_animation_frame(f.borrow().as_ref().unwrap());
         }));
         browser::request_animation_frame(
             g.borrow()
@@ -329,46 +1241,327 @@ impl GameLoop {
     }
 }
 
+#[derive(Clone)]
+struct MixerBuses {
+    master: GainNode,
+    music: GainNode,
+    sfx: GainNode,
+}
+
+impl MixerBuses {
+    fn new(context: &AudioContext) -> Result<Self> {
+        let master = create_gain(context, &context.destination())?;
+        Ok(MixerBuses {
+            music: create_gain(context, &master)?,
+            sfx: create_gain(context, &master)?,
+            master,
+        })
+    }
+
+    fn node(&self, bus: Bus) -> &GainNode {
+        match bus {
+            Bus::Master => &self.master,
+            Bus::Music => &self.music,
+            Bus::Sfx => &self.sfx,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum AudioBackend {
+    Web(AudioContext, MixerBuses),
+    Headless(Rc<RefCell<Vec<String>>>),
+}
+
+/// Browsers keep a new `AudioContext` suspended until the first click, tap
+/// or key press on the page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioState {
+    Suspended,
+    Running,
+    Closed,
+}
+
+impl From<AudioContextState> for AudioState {
+    fn from(state: AudioContextState) -> Self {
+        match state {
+            AudioContextState::Running => AudioState::Running,
+            AudioContextState::Closed => AudioState::Closed,
+            _ => AudioState::Suspended,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct PendingSound {
+    bus: Bus,
+    sound: Sound,
+    handle: SoundHandle,
+}
+
+// Clones share the context, the buses, the settings and the state, so the
+// mixer can be changed and the audio resumed through any of them.
 #[derive(Clone)]
 pub struct Audio {
-    context: AudioContext,
+    backend: AudioBackend,
+    mixer: Rc<RefCell<MixerSettings>>,
+    state: Rc<RefCell<AudioState>>,
+    pending: Rc<RefCell<Vec<PendingSound>>>,
 }
 
 impl Audio {
     pub fn new() -> Result<Self> {
-        Ok(Audio {
-            context: create_audio_context()?,
-        })
+        let context = create_audio_context()?;
+        let buses = MixerBuses::new(&context)?;
+        let audio = Audio {
+            state: Rc::new(RefCell::new(context.state().into())),
+            backend: AudioBackend::Web(context.clone(), buses),
+            mixer: Rc::new(RefCell::new(MixerSettings::default())),
+            pending: Rc::new(RefCell::new(vec![])),
+        };
+        audio.set_mixer(MixerSettings::default());
+        let state_audio = audio.clone();
+        let state_context = context.clone();
+        let on_state_change = browser::closure_wrap(Box::new(move || {
+            state_audio.set_state(state_context.state().into());
+        }) as Box<dyn FnMut()>);
+        context.set_onstatechange(Some(on_state_change.as_ref().unchecked_ref()));
+        on_state_change.forget();
+        let gesture_audio = audio.clone();
+        browser::on_user_gesture(move || gesture_audio.resume())?;
+        Ok(audio)
+    }
+
+    /// Audio that plays nothing and only remembers which sounds were requested.
+    pub fn headless() -> Self {
+        Audio {
+            backend: AudioBackend::Headless(Rc::new(RefCell::new(vec![]))),
+            mixer: Rc::new(RefCell::new(MixerSettings::default())),
+            state: Rc::new(RefCell::new(AudioState::Running)),
+            pending: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    /// Headless audio that, like a browser's before any gesture, waits for `resume`.
+    pub fn headless_suspended() -> Self {
+        let audio = Audio::headless();
+        audio.state.replace(AudioState::Suspended);
+        audio
     }
 
     pub async fn load_sound(&self, filename: &str) -> Result<Sound> {
-        let array_buffer = browser::fetch_array_buffer(filename).await?;
-        let audio_buffer = decode_audio_data(&self.context, &array_buffer).await?;
-        Ok(Sound {
-            buffer: audio_buffer,
-        })
+        match &self.backend {
+            AudioBackend::Web(context, _) => {
+                let array_buffer = browser::fetch_array_buffer(filename).await?;
+                let audio_buffer = decode_audio_data(context, &array_buffer).await?;
+                Ok(Sound {
+                    name: filename.into(),
+                    buffer: Some(audio_buffer),
+                })
+            }
+            AudioBackend::Headless(_) => Ok(Sound::headless(filename)),
+        }
     }
 
-    pub fn play_sound(&self, sound: &Sound, looping: LOOPING) -> Result<()> {
-        play_sound(&self.context, &sound.buffer, looping)
+    pub fn state(&self) -> AudioState {
+        *self.state.borrow()
     }
 
-    pub fn play_looping_sound(&self, sound: &Sound) -> Result<()> {
-        play_sound(&self.context, &sound.buffer, LOOPING::YES)
+    // Called from every user gesture. Browsers only let the context start in
+    // one, and it is a no-op once it runs.
+    pub fn resume(&self) {
+        match &self.backend {
+            AudioBackend::Web(context, _) if self.state() == AudioState::Suspended => {
+                if let Err(err) = context.resume() {
+                    log!("Could not resume the audio context {:#?}", err);
+                }
+            }
+            AudioBackend::Web(..) => {}
+            AudioBackend::Headless(_) => self.set_state(AudioState::Running),
+        }
+    }
+
+    fn set_state(&self, state: AudioState) {
+        self.state.replace(state);
+        if state == AudioState::Running {
+            self.pending
+                .take()
+                .into_iter()
+                .filter(|pending| !pending.handle.is_stopped())
+                .for_each(|pending| {
+                    if let Err(err) =
+                        self.start(pending.bus, &pending.sound, Looping::Yes, &pending.handle)
+                    {
+                        log!("Could not start {} {:#?}", pending.sound.name, err);
+                    }
+                });
+        }
+    }
+
+    /// Plays a sound effect on the sfx bus.
+    pub fn play_sound(&self, sound: &Sound, looping: Looping) -> Result<SoundHandle> {
+        self.play_on(Bus::Sfx, sound, looping)
+    }
+
+    /// Loops a track on the music bus.
+    pub fn play_music(&self, sound: &Sound) -> Result<SoundHandle> {
+        self.play_on(Bus::Music, sound, Looping::Yes)
+    }
+
+    // Until the audio runs, loops wait to start and one-shot sounds are
+    // dropped, since they would only play late.
+    fn play_on(&self, bus: Bus, sound: &Sound, looping: Looping) -> Result<SoundHandle> {
+        let handle = SoundHandle {
+            playback: Rc::new(RefCell::new(None)),
+            stopped: Rc::new(RefCell::new(false)),
+        };
+        match (self.state(), looping) {
+            (AudioState::Running, looping) => self.start(bus, sound, looping, &handle)?,
+            (_, Looping::Yes) => self.pending.borrow_mut().push(PendingSound {
+                bus,
+                sound: sound.clone(),
+                handle: handle.clone(),
+            }),
+            (_, Looping::No) => {}
+        }
+        Ok(handle)
+    }
+
+    fn start(&self, bus: Bus, sound: &Sound, looping: Looping, handle: &SoundHandle) -> Result<()> {
+        let playback = match (&self.backend, &sound.buffer) {
+            (AudioBackend::Web(context, buses), Some(buffer)) => {
+                let (source, gain) = play_sound(context, buffer, looping, buses.node(bus))?;
+                Playback::Web {
+                    context: context.clone(),
+                    source,
+                    gain,
+                }
+            }
+            (AudioBackend::Web(..), None) => {
+                return Err(anyhow!("Sound {} has no audio data", sound.name))
+            }
+            (AudioBackend::Headless(played), _) => {
+                played.borrow_mut().push(sound.name.to_string());
+                Playback::Headless
+            }
+        };
+        handle.playback.replace(Some(playback));
+        Ok(())
+    }
+
+    pub fn played(&self) -> Vec<String> {
+        match &self.backend {
+            AudioBackend::Web(..) => vec![],
+            AudioBackend::Headless(played) => played.borrow().clone(),
+        }
+    }
+
+    pub fn mixer(&self) -> MixerSettings {
+        *self.mixer.borrow()
+    }
+
+    /// Applies the volumes and mute to every bus, including sounds already playing.
+    pub fn set_mixer(&self, settings: MixerSettings) {
+        self.mixer.replace(settings);
+        if let AudioBackend::Web(context, buses) = &self.backend {
+            Bus::ALL.iter().for_each(|bus| {
+                if let Err(err) = set_gain(context, buses.node(*bus), settings.gain(*bus)) {
+                    log!("Could not set the {} volume {:#?}", bus.label(), err);
+                }
+            });
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Playback {
+    Web {
+        context: AudioContext,
+        source: AudioBufferSourceNode,
+        gain: GainNode,
+    },
+    Headless,
+}
+
+/// A sound that has been asked to play, for stopping or fading it out later.
+/// Stopping one that is still waiting for the audio to resume cancels it.
+#[derive(Clone)]
+pub struct SoundHandle {
+    playback: Rc<RefCell<Option<Playback>>>,
+    stopped: Rc<RefCell<bool>>,
+}
+
+impl SoundHandle {
+    pub fn stop(&self) -> Result<()> {
+        self.stopped.replace(true);
+        match &*self.playback.borrow() {
+            Some(Playback::Web { source, .. }) => stop_sound(source, 0.0),
+            Some(Playback::Headless) | None => Ok(()),
+        }
+    }
+
+    /// Ramps the sound down to silence over `seconds`, then stops it.
+    pub fn fade_out(&self, seconds: f64) -> Result<()> {
+        self.stopped.replace(true);
+        match &*self.playback.borrow() {
+            Some(Playback::Web {
+                context,
+                source,
+                gain,
+            }) => fade_out(context, source, gain, seconds),
+            Some(Playback::Headless) | None => Ok(()),
+        }
+    }
+
+    /// False while the sound waits for the audio to resume.
+    pub fn is_started(&self) -> bool {
+        self.playback.borrow().is_some()
+    }
+
+    /// True once the sound has been stopped or faded out, not when it ends by itself.
+    pub fn is_stopped(&self) -> bool {
+        *self.stopped.borrow()
     }
 }
 
 #[derive(Clone)]
 pub struct Sound {
-    buffer: AudioBuffer,
+    name: Rc<str>,
+    buffer: Option<AudioBuffer>,
+}
+
+impl Sound {
+    pub fn headless(name: &str) -> Self {
+        Sound {
+            name: name.into(),
+            buffer: None,
+        }
+    }
 }
 
 pub fn add_click_handler(elem: HtmlElement) -> UnboundedReceiver<()> {
     let (mut click_sender, click_receiver) = unbounded();
     let on_click = browser::closure_wrap(Box::new(move || {
-        click_sender.start_send(());
+        let _ = click_sender.start_send(());
     }) as Box<dyn FnMut()>);
     elem.set_onclick(Some(on_click.as_ref().unchecked_ref()));
     on_click.forget();
     click_receiver
 }
+
+pub fn click_events(id: &str) -> Result<UnboundedReceiver<()>> {
+    browser::find_html_element_by_id(id).map(add_click_handler)
+}
+
+/// Sends clicks on the button `id` through the game's input as `click_code(id)`,
+/// so recordings capture them and replays press them on the same tick.
+pub fn add_button(id: &str) -> Result<()> {
+    let elem = browser::find_html_element_by_id(id)?;
+    let code = click_code(id);
+    let on_click = browser::closure_wrap(Box::new(move || {
+        CLICKED_BUTTONS.with(|clicked| clicked.borrow_mut().push(code.clone()));
+    }) as Box<dyn FnMut()>);
+    elem.set_onclick(Some(on_click.as_ref().unchecked_ref()));
+    on_click.forget();
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use anyhow::{Error, Result};
+use futures::channel::mpsc::UnboundedReceiver;
+
+use crate::browser;
+use crate::engine::{self, KeyState, Point, Rect, Renderer, TextAlign, TextStyle, FOCUS_LOST};
+use crate::high_scores::escape_html;
+
+const BACKGROUND: &str = "#1d2b3a";
+const TITLE_STYLE: TextStyle = TextStyle {
+    font: "32px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Center,
+};
+
+pub fn error_ui(err: &Error) -> String {
+    format!(
+        "<div id='frame_error'><p>Something went wrong</p><p>{}</p><button id='resume'>Resume</button></div>",
+        escape_html(&format!("{:#}", err))
+    )
+}
+
+/// Shown in place of the game when an update or draw fails, so one bad frame
+/// doesn't take the whole page down. The game picks up where it left off on resume.
+pub struct ErrorScene {
+    // None when the error UI could not be shown, in which case the next key,
+    // button or tap resumes instead of the Resume button.
+    resume_event: Option<UnboundedReceiver<()>>,
+    keys_released: bool,
+}
+
+impl ErrorScene {
+    pub fn show(err: &Error) -> Self {
+        log!("Error running the game {:#}", err);
+        let resume_event = browser::draw_ui(&error_ui(err)).and_then(|_unit| {
+            // A UI whose button does nothing is worse than none at all.
+            engine::click_events("resume").inspect_err(|_err| {
+                if let Err(err) = browser::hide_ui() {
+                    log!("Error hiding the error UI {:#?}", err);
+                }
+            })
+        });
+        ErrorScene {
+            resume_event: resume_event
+                .map_err(|err| log!("Could not show the error UI {:#?}", err))
+                .ok(),
+            keys_released: false,
+        }
+    }
+
+    /// True once the player has asked to carry on, after the UI is gone.
+    pub fn resumed(&mut self, keystate: &KeyState) -> bool {
+        let resumed = match &mut self.resume_event {
+            Some(resume_event) => matches!(resume_event.try_next(), Ok(Some(()))),
+            None => {
+                // A key still held from before the error doesn't count.
+                let pressed = keystate.pressed_keys().any(|code| code != FOCUS_LOST);
+                let resumed = pressed && self.keys_released;
+                self.keys_released |= !pressed;
+                resumed
+            }
+        };
+        if resumed && self.resume_event.is_some() {
+            if let Err(err) = browser::hide_ui() {
+                log!("Error hiding the error UI {:#?}", err);
+            }
+        }
+        resumed
+    }
+
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        renderer.fill_rect(&Rect::new_from_x_y(0, 0, 600, 570), BACKGROUND);
+        renderer.draw_text(
+            "Something went wrong",
+            &Point { x: 300, y: 230 },
+            &TITLE_STYLE,
+        )
+    }
+}
@@ -1,96 +1,332 @@
 use std::rc::Rc;
 
+use crate::assets::{AssetManager, AssetManifest, BrowserLoader, LoadProgress, MANIFEST};
+use crate::debug;
+use crate::difficulty::{Difficulty, DifficultyCurve};
+use crate::editor::{self, Editor};
 use crate::engine::{
-    load_image, Audio, Game, Image, KeyState, Point, Rect, Renderer, Sheet, SpriteSheet,
+    Audio, AudioState, Camera, Game, KeyState, Point, Rect, Renderer, Sound, SoundHandle,
+    TextAlign, TextStyle,
 };
+use crate::high_scores::{escape_html, HighScoreTable};
+use crate::input::{Action, Actions, KeyBindings};
+use crate::mixer::{Bus, MixerSettings, VOLUME_STEP};
 use crate::obstacles::Obstacle;
+use crate::parallax::{Background, BackgroundFile};
 use crate::red_hat_boy::RedHatBoy;
-use crate::segments::{platform_and_stone, stone_and_platform};
+use crate::score::Score;
+use crate::segments::{SegmentFile, SegmentPool};
+use crate::store::{LocalStore, MemoryStore, Store};
 use crate::{browser, engine};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use futures::channel::mpsc::UnboundedReceiver;
-use rand::{thread_rng, Rng};
-use web_sys::HtmlImageElement;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::cell::{Cell, RefCell};
+
+const TIMELINE_MINIMUM: i32 = 1000;
+// The camera rises once the boy jumps higher than this below the top of the canvas.
+const CAMERA_TOP_MARGIN: i32 = 40;
+const KNOCK_OUT_SHAKE: i32 = 6;
+const KNOCK_OUT_SHAKE_TICKS: u16 = 20;
+const MUSIC_FADE_SECONDS: f64 = 1.5;
+const SEED_PARAMETER: &str = "seed";
+const PAUSED_SHADE: &str = "rgba(0, 0, 0, 0.5)";
+const PAUSED_TITLE_STYLE: TextStyle = TextStyle {
+    font: "48px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Center,
+};
+const PAUSED_HINT_STYLE: TextStyle = TextStyle {
+    font: "20px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Center,
+};
+pub const SOUND_HINT: &str = "Click or press a key to enable sound";
+const SOUND_HINT_STYLE: TextStyle = TextStyle {
+    font: "16px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Center,
+};
 
-const TIMELINE_MINIMUM: i16 = 1000;
-const OBSTACLE_BUFFER: i16 = 20;
+thread_local! {
+    static SEED_OVERRIDE: Cell<Option<u32>> = const { Cell::new(None) };
+}
 
-fn rightmost(obstacle_list: &[Box<dyn Obstacle>]) -> i16 {
+/// Forces every following walk to use `seed` instead of a random one.
+pub fn set_seed(seed: u32) {
+    SEED_OVERRIDE.with(|seed_override| seed_override.set(Some(seed)));
+}
+
+pub fn query_seed() -> Option<u32> {
+    browser::query_parameter(SEED_PARAMETER).and_then(|seed| seed.parse().ok())
+}
+
+fn next_seed() -> u32 {
+    SEED_OVERRIDE.with(Cell::get).unwrap_or_else(rand::random)
+}
+
+fn rightmost(obstacle_list: &[Box<dyn Obstacle>]) -> i32 {
     obstacle_list
         .iter()
         .map(|obstacle| obstacle.right())
-        .max_by(|x, y| x.cmp(&y))
+        .max_by(|x, y| x.cmp(y))
         .unwrap_or(0)
 }
 
+// Keeps the boy where he starts on screen as he runs on through the world.
+fn walk_camera(boy: &RedHatBoy) -> Camera {
+    let mut camera = Camera::following(boy.position());
+    camera.follow_vertically(CAMERA_TOP_MARGIN);
+    camera
+}
+
 pub struct Walk {
-    obstacle_sheet: Rc<SpriteSheet>,
+    segments: Rc<SegmentPool>,
+    difficulty: Rc<DifficultyCurve>,
+    // The fraction of a pixel a faster-than-running pace has yet to cover.
+    travel_remainder: f32,
     boy: RedHatBoy,
-    backgrounds: [Image; 2],
+    camera: Camera,
+    background: Background,
+    // Obstacles stay where they were placed in the world, and the right edge
+    // of the last one is where the next segment starts from.
     obstacles: Vec<Box<dyn Obstacle>>,
-    stone: HtmlImageElement,
-    timeline: i16,
+    timeline: i32,
+    seed: u32,
+    rng: StdRng,
+    score: Score,
+    music: Option<Sound>,
+    music_playback: Option<SoundHandle>,
 }
 
 impl Walk {
-    fn velocity(&self) -> i16 {
-        -self.boy.walking_speed()
-    }
-
-    fn generate_next_segment(&mut self) {
-        let mut rng = thread_rng();
-        let next_segment = rng.gen_range(0..2);
-        let mut next_obstacles = match next_segment {
-            0 => stone_and_platform(
-                self.stone.clone(),
-                self.obstacle_sheet.clone(),
-                self.timeline + OBSTACLE_BUFFER,
-            ),
-            1 => platform_and_stone(
-                self.stone.clone(),
-                self.obstacle_sheet.clone(),
-                self.timeline + OBSTACLE_BUFFER,
-            ),
-            _ => vec![],
-        };
+    pub fn new(
+        boy: RedHatBoy,
+        background: Background,
+        segments: Rc<SegmentPool>,
+        difficulty: Rc<DifficultyCurve>,
+        seed: u32,
+    ) -> Self {
+        let starting_obstacles = segments.create(0, 0);
+        let timeline = rightmost(&starting_obstacles);
+        let camera = walk_camera(&boy);
+        Walk {
+            segments,
+            difficulty,
+            travel_remainder: 0.0,
+            boy,
+            camera,
+            background,
+            obstacles: starting_obstacles,
+            timeline,
+            seed,
+            rng: StdRng::seed_from_u64(seed.into()),
+            score: Score::new(),
+            music: None,
+            music_playback: None,
+        }
+    }
+
+    /// Loops `music` until the boy is knocked out, and again on every new walk.
+    pub fn play_music(&mut self, music: Sound) {
+        self.music = Some(music);
+        self.restart_music();
+    }
+
+    fn restart_music(&mut self) {
+        self.stop_music();
+        if let Some(music) = &self.music {
+            match self.boy.audio().play_music(music) {
+                Ok(playback) => self.music_playback = Some(playback),
+                Err(err) => log!("Error playing background music {:#?}", err),
+            }
+        }
+    }
+
+    fn stop_music(&mut self) {
+        if let Some(playback) = self.music_playback.take() {
+            if let Err(err) = playback.stop() {
+                log!("Error stopping background music {:#?}", err);
+            }
+        }
+    }
+
+    pub fn music_playback(&self) -> Option<&SoundHandle> {
+        self.music_playback.as_ref()
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn score(&self) -> &Score {
+        &self.score
+    }
+
+    pub fn boy(&self) -> &RedHatBoy {
+        &self.boy
+    }
+
+    pub fn boy_mut(&mut self) -> &mut RedHatBoy {
+        &mut self.boy
+    }
+
+    pub fn obstacle_count(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    pub fn timeline(&self) -> i32 {
+        self.timeline
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty.at(self.score.distance())
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    // The boy's running speed scaled up by the difficulty curve.
+    fn speed(&self) -> f32 {
+        self.boy.walking_speed() as f32 * self.difficulty().speed
+    }
+
+    // Whole pixels are travelled each tick and the rest carries over to the next.
+    fn travel(&mut self) -> i32 {
+        let exact = self.speed() + self.travel_remainder;
+        let pixels = exact.floor();
+        self.travel_remainder = exact - pixels;
+        pixels as i32
+    }
+
+    pub fn generate_next_segment(&mut self) {
+        let difficulty = self.difficulty();
+        let next_segment = self
+            .segments
+            .choose(&mut self.rng, difficulty.segment_difficulty);
+        let mut next_obstacles = self
+            .segments
+            .create(next_segment, self.timeline + difficulty.obstacle_buffer);
         self.timeline = rightmost(&next_obstacles);
         self.obstacles.append(&mut next_obstacles);
     }
 
-    fn draw(&self, renderer: &Renderer) {
-        self.backgrounds.iter().for_each(|background| {
-            background.draw(renderer);
-        });
-        self.boy.draw(renderer);
+    pub fn update(&mut self) {
+        self.boy.update();
+        if self.boy.knocked_out() {
+            if let Some(playback) = self.music_playback.take() {
+                if let Err(err) = playback.fade_out(MUSIC_FADE_SECONDS) {
+                    log!("Error fading out background music {:#?}", err);
+                }
+            }
+        }
+        let distance = self.travel();
+        // Anything the camera has already passed is gone for good.
+        let camera_left = self.camera.x();
+        self.obstacles
+            .retain(|obstacle| obstacle.right() > camera_left);
+        let boy_left = self.boy.bounding_box().x();
+        self.boy.travel(distance);
+        let was_falling = self.boy.falling();
+        let boy_left_now = self.boy.bounding_box().x();
+        let mut cleared = 0;
         self.obstacles.iter().for_each(|obstacle| {
-            obstacle.draw(renderer);
+            obstacle.check_intersection(&mut self.boy);
+            if obstacle.right() >= boy_left && obstacle.right() < boy_left_now {
+                cleared += 1;
+            }
         });
+        self.camera.update();
+        if self.boy.falling() && !was_falling {
+            self.camera.shake(KNOCK_OUT_SHAKE, KNOCK_OUT_SHAKE_TICKS);
+        }
+        self.camera.follow(&self.boy.position());
+        self.score.add_distance(distance);
+        (0..cleared).for_each(|_| self.score.obstacle_cleared());
+        if self.timeline - self.camera.x() < TIMELINE_MINIMUM {
+            self.generate_next_segment()
+        }
     }
 
-    fn knocked_out(&self) -> bool {
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        self.background.draw_behind(renderer, &self.camera)?;
+        let world = self.camera.view(renderer);
+        self.boy.draw(&world)?;
+        self.obstacles
+            .iter()
+            .try_for_each(|obstacle| obstacle.draw(&world))?;
+        self.background.draw_in_front(renderer, &self.camera)?;
+        self.score.draw(renderer)
+    }
+
+    pub fn knocked_out(&self) -> bool {
         self.boy.knocked_out()
     }
 
-    fn reset(walk: Self) -> Self {
-        let starting_obstacles =
-            stone_and_platform(walk.stone.clone(), walk.obstacle_sheet.clone(), 0);
-        let timeline = rightmost(&starting_obstacles);
+    pub fn draw_debug(&self, renderer: &dyn Renderer) -> Result<()> {
+        let world = self.camera.view(renderer);
+        self.obstacles.iter().for_each(|obstacle| {
+            debug::draw_bounding_boxes(&world, &obstacle.bounding_boxes(), debug::OBSTACLE_COLOR)
+        });
+        debug::draw_bounding_boxes(&world, &[self.boy.bounding_box()], debug::PLAYER_COLOR);
+        debug::draw_lines(
+            renderer,
+            &[
+                format!("state {}", self.boy.state_name()),
+                format!("frame {}", self.boy.frame()),
+                format!("velocity {:.1}, {}", self.speed(), self.boy.velocity_y()),
+                format!(
+                    "camera {}, {}",
+                    self.camera.position().x,
+                    self.camera.position().y
+                ),
+                format!("timeline {}", self.timeline),
+                format!("obstacles {}", self.obstacles.len()),
+            ],
+        )
+    }
 
-        Walk {
-            boy: RedHatBoy::reset(walk.boy),
-            backgrounds: walk.backgrounds,
+    pub fn reset(walk: Self) -> Self {
+        let segments = walk.segments.clone();
+        Walk::with_segments(walk, segments)
+    }
+
+    /// Starts over like `reset`, but on a different set of segments.
+    pub fn with_segments(mut walk: Self, segments: Rc<SegmentPool>) -> Self {
+        walk.stop_music();
+        let starting_obstacles = segments.create(0, 0);
+        let timeline = rightmost(&starting_obstacles);
+        let seed = next_seed();
+        log!("Starting a new walk with seed {}", seed);
+
+        let boy = RedHatBoy::reset(walk.boy);
+        let camera = walk_camera(&boy);
+        let mut walk = Walk {
+            difficulty: walk.difficulty,
+            travel_remainder: 0.0,
+            boy,
+            camera,
+            background: walk.background,
             obstacles: starting_obstacles,
-            obstacle_sheet: walk.obstacle_sheet,
-            stone: walk.stone,
+            segments,
             timeline,
-        }
+            seed,
+            rng: StdRng::seed_from_u64(seed.into()),
+            score: Score::new(),
+            music: walk.music,
+            music_playback: None,
+        };
+        walk.restart_music();
+        walk
     }
 }
 
 enum ReadyEndState {
     Complete(WalkTheDogState<Walking>),
     Continue(WalkTheDogState<Ready>),
+    Settings(WalkTheDogState<Settings>),
 }
 
 impl From<ReadyEndState> for WalkTheDogStateMachine {
@@ -98,6 +334,7 @@ impl From<ReadyEndState> for WalkTheDogStateMachine {
         match state {
             ReadyEndState::Complete(walking) => walking.into(),
             ReadyEndState::Continue(ready) => ready.into(),
+            ReadyEndState::Settings(settings) => settings.into(),
         }
     }
 }
@@ -105,17 +342,33 @@ impl From<ReadyEndState> for WalkTheDogStateMachine {
 struct WalkTheDogState<T> {
     _state: T,
     walk: Walk,
+    store: Rc<dyn Store>,
+    bindings: KeyBindings,
 }
 
 impl<T> WalkTheDogState<T> {
-    fn draw(&self, renderer: &Renderer) {
-        self.walk.draw(renderer);
+    fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        self.walk.draw(renderer)
+    }
+
+    fn change_mixer(&self, mixer: MixerSettings) {
+        self.walk.boy.audio().set_mixer(mixer);
+        mixer.save(&*self.store);
+    }
+
+    fn toggle_mute(&self) {
+        let mixer = self.walk.boy.audio().mixer();
+        self.change_mixer(MixerSettings {
+            muted: !mixer.muted,
+            ..mixer
+        });
     }
 }
 
 enum WalkingEndState {
     Complete(WalkTheDogState<GameOver>),
     Continue(WalkTheDogState<Walking>),
+    Pause(WalkTheDogState<Paused>),
 }
 
 impl From<WalkingEndState> for WalkTheDogStateMachine {
@@ -123,6 +376,35 @@ impl From<WalkingEndState> for WalkTheDogStateMachine {
         match state {
             WalkingEndState::Complete(game_over) => game_over.into(),
             WalkingEndState::Continue(walking) => walking.into(),
+            WalkingEndState::Pause(paused) => paused.into(),
+        }
+    }
+}
+
+enum PausedEndState {
+    Complete(WalkTheDogState<Walking>),
+    Continue(WalkTheDogState<Paused>),
+}
+
+impl From<PausedEndState> for WalkTheDogStateMachine {
+    fn from(state: PausedEndState) -> Self {
+        match state {
+            PausedEndState::Complete(walking) => walking.into(),
+            PausedEndState::Continue(paused) => paused.into(),
+        }
+    }
+}
+
+enum SettingsEndState {
+    Complete(WalkTheDogState<Ready>),
+    Continue(WalkTheDogState<Settings>),
+}
+
+impl From<SettingsEndState> for WalkTheDogStateMachine {
+    fn from(state: SettingsEndState) -> Self {
+        match state {
+            SettingsEndState::Complete(ready) => ready.into(),
+            SettingsEndState::Continue(settings) => settings.into(),
         }
     }
 }
@@ -143,20 +425,124 @@ impl From<GameOverEndState> for WalkTheDogStateMachine {
 
 struct Ready;
 struct Walking;
+struct Paused;
+struct Settings {
+    capturing: Option<Action>,
+}
 struct GameOver {
-    new_game_event: UnboundedReceiver<()>,
+    high_scores: HighScoreTable,
+    rank: Option<usize>,
 }
 
-impl GameOver {
-    fn new_game_pressed(&mut self) -> bool {
-        matches!(self.new_game_event.try_next(), Ok(Some(())))
+impl Ready {
+    fn new() -> Self {
+        if let Err(err) =
+            browser::draw_ui("<div id='ready'><button id='open_settings'>Settings</button></div>")
+                .and_then(|_unit| engine::add_button("open_settings"))
+        {
+            log!("Could not show the controls button {:#?}", err);
+        }
+        Ready
+    }
+}
+
+impl Settings {
+    fn show(bindings: &KeyBindings, mixer: &MixerSettings, capturing: Option<Action>) -> Self {
+        let buttons =
+            browser::draw_ui(&settings_ui(bindings, mixer, capturing)).and_then(|_unit| {
+                Action::ALL
+                    .iter()
+                    .map(|action| rebind_id(*action))
+                    .chain(volume_buttons().map(|(bus, step)| volume_id(bus, step)))
+                    .chain(["toggle_mute", "reset_controls", "close_settings"].map(String::from))
+                    .try_for_each(|id| engine::add_button(&id))
+            });
+        if let Err(err) = buttons {
+            log!("Could not show the controls UI {:#?}", err);
+        }
+        Settings { capturing }
     }
 }
 
+fn volume_buttons() -> impl Iterator<Item = (Bus, f32)> {
+    Bus::ALL
+        .into_iter()
+        .flat_map(|bus| [(bus, -VOLUME_STEP), (bus, VOLUME_STEP)])
+}
+
+fn volume_pressed(actions: &Actions) -> Option<(Bus, f32)> {
+    volume_buttons().find(|(bus, step)| actions.clicked(&volume_id(*bus, *step)))
+}
+
+fn rebind_pressed(actions: &Actions) -> Option<Action> {
+    Action::ALL
+        .into_iter()
+        .find(|action| actions.clicked(&rebind_id(*action)))
+}
+
+fn rebind_id(action: Action) -> String {
+    format!("rebind_{}", action.label().to_lowercase())
+}
+
+fn volume_id(bus: Bus, step: f32) -> String {
+    let bus = match bus {
+        Bus::Master => "master",
+        Bus::Music => "music",
+        Bus::Sfx => "sfx",
+    };
+    let direction = if step < 0.0 { "down" } else { "up" };
+    format!("volume_{}_{}", direction, bus)
+}
+
+fn settings_ui(bindings: &KeyBindings, mixer: &MixerSettings, capturing: Option<Action>) -> String {
+    let rows: String = Action::ALL
+        .iter()
+        .map(|action| {
+            let keys = if capturing == Some(*action) {
+                "Press a key".to_string()
+            } else {
+                escape_html(&bindings.labels(*action).join(", "))
+            };
+            format!(
+                "<tr><td>{}</td><td>{}</td><td><button id='{}'>Change</button></td></tr>",
+                action.label(),
+                keys,
+                rebind_id(*action)
+            )
+        })
+        .collect();
+    let volumes: String = Bus::ALL
+        .iter()
+        .map(|bus| {
+            format!(
+                "<tr><td>{}</td><td>{}%</td><td><button id='{}'>-</button><button id='{}'>+</button></td></tr>",
+                bus.label(),
+                (mixer.volume(*bus) * 100.0).round(),
+                volume_id(*bus, -VOLUME_STEP),
+                volume_id(*bus, VOLUME_STEP)
+            )
+        })
+        .collect();
+    format!(
+        "<div id='settings'><table>{}{}</table><p>{}{}{}</p></div>",
+        rows,
+        volumes,
+        if mixer.muted {
+            "<button id='toggle_mute'>Unmute</button>"
+        } else {
+            "<button id='toggle_mute'>Mute</button>"
+        },
+        "<button id='reset_controls'>Defaults</button>",
+        "<button id='close_settings'>Done</button>"
+    )
+}
+
 impl WalkTheDogState<Ready> {
-    fn update(mut self, keystate: &KeyState) -> ReadyEndState {
+    fn update(mut self, actions: &Actions) -> ReadyEndState {
         self.walk.boy.update();
-        if keystate.is_pressed("ArrowRight") {
+        if actions.clicked("open_settings") {
+            ReadyEndState::Settings(self.open_settings())
+        } else if actions.is_held(Action::Run) {
             ReadyEndState::Complete(self.start_running())
         } else {
             ReadyEndState::Continue(self)
@@ -164,10 +550,27 @@ impl WalkTheDogState<Ready> {
     }
 
     fn start_running(mut self) -> WalkTheDogState<Walking> {
+        if let Err(err) = browser::hide_ui() {
+            log!("Error hiding the controls button {:#?}", err);
+        }
         self.run_right();
         WalkTheDogState {
             _state: Walking,
             walk: self.walk,
+            store: self.store,
+            bindings: self.bindings,
+        }
+    }
+
+    fn open_settings(self) -> WalkTheDogState<Settings> {
+        if let Err(err) = browser::hide_ui() {
+            log!("Error hiding the controls button {:#?}", err);
+        }
+        WalkTheDogState {
+            _state: Settings::show(&self.bindings, &self.walk.boy.audio().mixer(), None),
+            walk: self.walk,
+            store: self.store,
+            bindings: self.bindings,
         }
     }
 
@@ -175,43 +578,29 @@ impl WalkTheDogState<Ready> {
         self.walk.boy.run_right();
     }
 
-    fn new(walk: Walk) -> WalkTheDogState<Ready> {
+    fn new(walk: Walk, store: Rc<dyn Store>) -> WalkTheDogState<Ready> {
+        let bindings = KeyBindings::load(&*store);
         WalkTheDogState {
-            _state: Ready,
+            _state: Ready::new(),
             walk,
+            store,
+            bindings,
         }
     }
 }
 
 impl WalkTheDogState<Walking> {
-    fn update(mut self, keystate: &KeyState) -> WalkingEndState {
-        if keystate.is_pressed("ArrowDown") {
-            self.walk.boy.slide();
+    fn update(mut self, actions: &Actions) -> WalkingEndState {
+        if actions.focus_lost() || actions.was_pressed(Action::Pause) {
+            return WalkingEndState::Pause(self.pause());
         }
-        if keystate.is_pressed("Space") {
+        if actions.is_held(Action::Jump) {
             self.walk.boy.jump();
         }
-        self.walk.boy.update();
-        let walking_speed = self.walk.velocity();
-        let [first_background, second_background] = &mut self.walk.backgrounds;
-        first_background.move_horizontally(walking_speed);
-        second_background.move_horizontally(walking_speed);
-        if first_background.right() < 0 {
-            first_background.set_x(second_background.right());
-        }
-        if second_background.right() < 0 {
-            second_background.set_x(first_background.right());
-        }
-        self.walk.obstacles.retain(|obstacle| obstacle.right() > 0);
-        self.walk.obstacles.iter_mut().for_each(|obstacle| {
-            obstacle.move_horizontally(walking_speed);
-            obstacle.check_intersection(&mut self.walk.boy);
-        });
-        if self.walk.timeline < TIMELINE_MINIMUM {
-            self.walk.generate_next_segment()
-        } else {
-            self.walk.timeline += walking_speed;
+        if actions.is_held(Action::Slide) {
+            self.walk.boy.slide();
         }
+        self.walk.update();
         if self.walk.knocked_out() {
             WalkingEndState::Complete(self.end_game())
         } else {
@@ -219,34 +608,172 @@ impl WalkTheDogState<Walking> {
         }
     }
 
+    fn pause(self) -> WalkTheDogState<Paused> {
+        WalkTheDogState {
+            _state: Paused,
+            walk: self.walk,
+            store: self.store,
+            bindings: self.bindings,
+        }
+    }
+
     fn end_game(self) -> WalkTheDogState<GameOver> {
-        let receiver = browser::draw_ui("<button id='new_game'>New Game</button")
-            .and_then(|_unit| browser::find_html_element_by_id("new_game"))
-            .map(|element| engine::add_click_handler(element))
-            .unwrap();
+        let score = *self.walk.score();
+        let mut high_scores = HighScoreTable::load(self.store.clone());
+        let rank = high_scores.submit(
+            score.distance(),
+            score.total(),
+            self.walk.seed(),
+            &browser::today().unwrap_or_default(),
+        );
+        if let Err(err) = browser::draw_ui(&game_over_ui(&score, &high_scores, rank))
+            .and_then(|_unit| engine::add_button("new_game"))
+        {
+            log!("Could not show the game over UI {:#?}", err);
+        }
         WalkTheDogState {
-            _state: GameOver {
-                new_game_event: receiver,
-            },
+            _state: GameOver { high_scores, rank },
             walk: self.walk,
+            store: self.store,
+            bindings: self.bindings,
+        }
+    }
+}
+
+fn game_over_ui(score: &Score, high_scores: &HighScoreTable, rank: Option<usize>) -> String {
+    let name_input = match rank {
+        Some(_) => format!(
+            "<input id='player_name' maxlength='12' value='{}'>",
+            escape_html(high_scores.player_name())
+        ),
+        None => String::new(),
+    };
+    format!(
+        "<div id='game_over'><p>{} m</p><p>Score {}</p>{}{}{}</div>",
+        score.distance(),
+        score.total(),
+        high_scores.to_html(rank),
+        name_input,
+        "<button id='new_game'>New Game</button>"
+    )
+}
+
+impl WalkTheDogState<Paused> {
+    // Losing focus while already paused must not resume the walk.
+    fn update(self, actions: &Actions) -> PausedEndState {
+        if actions.was_pressed(Action::Pause) {
+            PausedEndState::Complete(self.resume())
+        } else {
+            PausedEndState::Continue(self)
+        }
+    }
+
+    fn resume(self) -> WalkTheDogState<Walking> {
+        WalkTheDogState {
+            _state: Walking,
+            walk: self.walk,
+            store: self.store,
+            bindings: self.bindings,
+        }
+    }
+
+    fn draw_overlay(&self, renderer: &dyn Renderer) -> Result<()> {
+        renderer.fill_rect(&Rect::new_from_x_y(0, 0, 600, 570), PAUSED_SHADE);
+        renderer.draw_text("Paused", &Point { x: 300, y: 260 }, &PAUSED_TITLE_STYLE)?;
+        renderer.draw_text(
+            &format!(
+                "Press {} to resume",
+                self.bindings.labels(Action::Pause).join(" or ")
+            ),
+            &Point { x: 300, y: 300 },
+            &PAUSED_HINT_STYLE,
+        )
+    }
+}
+
+impl WalkTheDogState<Settings> {
+    // The walk stays frozen behind the controls screen.
+    fn update(mut self, actions: &Actions) -> SettingsEndState {
+        if let Some(action) = self._state.capturing {
+            match actions.pressed_key() {
+                Some("Escape") => self.show_settings(None),
+                Some(code) => {
+                    self.bindings.bind(action, code);
+                    self.bindings.save(&*self.store);
+                    self.show_settings(None);
+                }
+                None => {}
+            }
+            SettingsEndState::Continue(self)
+        } else if actions.clicked("close_settings") {
+            SettingsEndState::Complete(self.close_settings())
+        } else if actions.clicked("reset_controls") {
+            self.bindings = KeyBindings::default();
+            self.bindings.save(&*self.store);
+            self.show_settings(None);
+            SettingsEndState::Continue(self)
+        } else if let Some((bus, step)) = volume_pressed(actions) {
+            let mut mixer = self.walk.boy.audio().mixer();
+            mixer.set_volume(bus, mixer.volume(bus) + step);
+            self.change_mixer(mixer);
+            self.show_settings(None);
+            SettingsEndState::Continue(self)
+        } else if actions.clicked("toggle_mute") {
+            self.toggle_mute();
+            self.show_settings(None);
+            SettingsEndState::Continue(self)
+        } else {
+            if let Some(action) = rebind_pressed(actions) {
+                self.show_settings(Some(action));
+            }
+            SettingsEndState::Continue(self)
+        }
+    }
+
+    fn show_settings(&mut self, capturing: Option<Action>) {
+        if let Err(err) = browser::hide_ui() {
+            log!("Error hiding the controls UI {:#?}", err);
+        }
+        self._state = Settings::show(&self.bindings, &self.walk.boy.audio().mixer(), capturing);
+    }
+
+    fn close_settings(self) -> WalkTheDogState<Ready> {
+        if let Err(err) = browser::hide_ui() {
+            log!("Error hiding the controls UI {:#?}", err);
+        }
+        WalkTheDogState {
+            _state: Ready::new(),
+            walk: self.walk,
+            store: self.store,
+            bindings: self.bindings,
         }
     }
 }
 
 impl WalkTheDogState<GameOver> {
-    fn update(mut self) -> GameOverEndState {
-        if self._state.new_game_pressed() {
+    fn update(self, actions: &Actions) -> GameOverEndState {
+        if actions.clicked("new_game") || actions.was_pressed(Action::Confirm) {
             GameOverEndState::Complete(self.new_game())
         } else {
             GameOverEndState::Continue(self)
         }
     }
 
-    fn new_game(self) -> WalkTheDogState<Ready> {
-        browser::hide_ui();
+    fn new_game(mut self) -> WalkTheDogState<Ready> {
+        if let Some(rank) = self._state.rank {
+            match browser::input_value("player_name") {
+                Ok(name) => self._state.high_scores.rename(rank, &name),
+                Err(err) => log!("Could not read the player name {:#?}", err),
+            }
+        }
+        if let Err(err) = browser::hide_ui() {
+            log!("Error hiding the game over UI {:#?}", err);
+        }
         WalkTheDogState {
-            _state: Ready,
+            _state: Ready::new(),
             walk: Walk::reset(self.walk),
+            store: self.store,
+            bindings: self.bindings,
         }
     }
 }
@@ -263,6 +790,18 @@ impl From<WalkTheDogState<Walking>> for WalkTheDogStateMachine {
     }
 }
 
+impl From<WalkTheDogState<Paused>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<Paused>) -> Self {
+        WalkTheDogStateMachine::Paused(state)
+    }
+}
+
+impl From<WalkTheDogState<Settings>> for WalkTheDogStateMachine {
+    fn from(state: WalkTheDogState<Settings>) -> Self {
+        WalkTheDogStateMachine::Settings(state)
+    }
+}
+
 impl From<WalkTheDogState<GameOver>> for WalkTheDogStateMachine {
     fn from(state: WalkTheDogState<GameOver>) -> Self {
         WalkTheDogStateMachine::GameOver(state)
@@ -272,103 +811,197 @@ impl From<WalkTheDogState<GameOver>> for WalkTheDogStateMachine {
 enum WalkTheDogStateMachine {
     Ready(WalkTheDogState<Ready>),
     Walking(WalkTheDogState<Walking>),
+    Paused(WalkTheDogState<Paused>),
+    Settings(WalkTheDogState<Settings>),
     GameOver(WalkTheDogState<GameOver>),
 }
 
 impl WalkTheDogStateMachine {
-    fn update(self, keystate: &KeyState) -> Self {
+    fn update(self, keystate: &KeyState, last_keystate: &KeyState) -> Self {
+        let actions = self.bindings().actions(keystate, last_keystate);
+        // Not while a key is being bound or a name typed in. The controls
+        // screen has a button of its own.
+        if actions.was_pressed(Action::Mute) {
+            match &self {
+                WalkTheDogStateMachine::Ready(state) => state.toggle_mute(),
+                WalkTheDogStateMachine::Walking(state) => state.toggle_mute(),
+                WalkTheDogStateMachine::Paused(state) => state.toggle_mute(),
+                WalkTheDogStateMachine::Settings(_) | WalkTheDogStateMachine::GameOver(_) => {}
+            }
+        }
         match self {
-            WalkTheDogStateMachine::Ready(state) => state.update(keystate).into(),
-            WalkTheDogStateMachine::Walking(state) => state.update(keystate).into(),
-            WalkTheDogStateMachine::GameOver(state) => state.update().into(),
+            WalkTheDogStateMachine::Ready(state) => state.update(&actions).into(),
+            WalkTheDogStateMachine::Walking(state) => state.update(&actions).into(),
+            WalkTheDogStateMachine::Paused(state) => state.update(&actions).into(),
+            WalkTheDogStateMachine::Settings(state) => state.update(&actions).into(),
+            WalkTheDogStateMachine::GameOver(state) => state.update(&actions).into(),
         }
     }
 
-    fn draw(&self, renderer: &Renderer) {
+    fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
         match self {
             WalkTheDogStateMachine::Ready(state) => state.draw(renderer),
             WalkTheDogStateMachine::Walking(state) => state.draw(renderer),
+            WalkTheDogStateMachine::Paused(state) => {
+                state.draw(renderer)?;
+                state.draw_overlay(renderer)
+            }
+            WalkTheDogStateMachine::Settings(state) => state.draw(renderer),
             WalkTheDogStateMachine::GameOver(state) => state.draw(renderer),
+        }?;
+        // A gamepad button doesn't count as a gesture, so this can outlast the start screen.
+        if self.walk().boy().audio().state() == AudioState::Suspended {
+            renderer.draw_text(SOUND_HINT, &Point { x: 300, y: 550 }, &SOUND_HINT_STYLE)?;
+        }
+        Ok(())
+    }
+
+    fn walk(&self) -> &Walk {
+        match self {
+            WalkTheDogStateMachine::Ready(state) => &state.walk,
+            WalkTheDogStateMachine::Walking(state) => &state.walk,
+            WalkTheDogStateMachine::Paused(state) => &state.walk,
+            WalkTheDogStateMachine::Settings(state) => &state.walk,
+            WalkTheDogStateMachine::GameOver(state) => &state.walk,
+        }
+    }
+
+    fn bindings(&self) -> &KeyBindings {
+        match self {
+            WalkTheDogStateMachine::Ready(state) => &state.bindings,
+            WalkTheDogStateMachine::Walking(state) => &state.bindings,
+            WalkTheDogStateMachine::Paused(state) => &state.bindings,
+            WalkTheDogStateMachine::Settings(state) => &state.bindings,
+            WalkTheDogStateMachine::GameOver(state) => &state.bindings,
         }
     }
 
-    fn new(walk: Walk) -> Self {
-        WalkTheDogStateMachine::Ready(WalkTheDogState::new(walk))
+    fn new(walk: Walk, store: Rc<dyn Store>) -> Self {
+        WalkTheDogStateMachine::Ready(WalkTheDogState::new(walk, store))
     }
 }
 
+#[derive(Default)]
 pub struct WalkTheDog {
     machine: Option<WalkTheDogStateMachine>,
+    last_keystate: KeyState,
+    // Kept between attempts, so retrying after a failed load only fetches what failed.
+    assets: RefCell<AssetManager>,
+    // Also kept, as each one opens an AudioContext and listens for the first gesture.
+    audio: RefCell<Option<Audio>>,
 }
 
 impl WalkTheDog {
     pub fn new() -> Self {
-        WalkTheDog { machine: None }
+        WalkTheDog::default()
+    }
+
+    fn audio(&self) -> Result<Audio> {
+        if let Some(audio) = self.audio.borrow().as_ref() {
+            return Ok(audio.clone());
+        }
+        let audio = Audio::new()?;
+        audio.set_mixer(MixerSettings::load(&LocalStore));
+        self.audio.replace(Some(audio.clone()));
+        Ok(audio)
+    }
+}
+
+impl WalkTheDog {
+    pub fn with_store(walk: Walk, store: Rc<dyn Store>) -> Self {
+        WalkTheDog {
+            machine: Some(WalkTheDogStateMachine::new(walk, store)),
+            last_keystate: KeyState::new(),
+            assets: RefCell::new(AssetManager::new()),
+            audio: RefCell::new(None),
+        }
+    }
+}
+
+impl From<Walk> for WalkTheDog {
+    fn from(walk: Walk) -> Self {
+        WalkTheDog::with_store(walk, Rc::new(MemoryStore::new()))
     }
 }
 
 #[async_trait(?Send)]
 impl Game for WalkTheDog {
-    async fn initialize(&self) -> Result<Box<dyn Game>> {
+    async fn initialize(&self, progress: &LoadProgress) -> Result<Box<dyn Game>> {
         match self.machine {
             None => {
-                let json = browser::fetch_json("rhb.json").await?;
-                let audio = Audio::new()?;
-                let sound = audio.load_sound("SFX_Jump_23.mp3").await?;
-                let background_music = audio.load_sound("background_song.mp3").await?;
-                audio.play_looping_sound(&background_music);
-                let rhb = RedHatBoy::new(
-                    json.into_serde::<Sheet>()?,
-                    load_image("rhb.png").await?,
-                    audio,
-                    sound,
+                let audio = self.audio()?;
+                let manifest = AssetManifest::from_json(&browser::fetch_text(MANIFEST).await?)?;
+                let mut assets = self.assets.take();
+                let loaded = assets
+                    .load(&manifest, &BrowserLoader::new(audio.clone()), progress)
+                    .await;
+                self.assets.replace(assets);
+                loaded?;
+                let assets = self.assets.borrow();
+                let (rhb_sheet, rhb_image) = assets.sheet("rhb")?;
+                let rhb = RedHatBoy::new(rhb_sheet, rhb_image, audio, assets.sound("jump")?);
+                let background = Background::new(
+                    &BackgroundFile::from_json(assets.text("background_layers")?)?,
+                    |key| assets.image(key),
+                )?;
+                let segments = SegmentPool::new(
+                    SegmentFile::from_json(assets.text("segments")?)?,
+                    |key| assets.image(key),
+                    assets.sprite_sheet("tiles")?,
+                )?;
+                let seed = next_seed();
+                log!("Starting a new walk with seed {}", seed);
+                let difficulty = DifficultyCurve::from_json(assets.text("difficulty")?)?;
+                let mut walk = Walk::new(
+                    rhb,
+                    background,
+                    Rc::new(segments),
+                    Rc::new(difficulty),
+                    seed,
                 );
-                let background = load_image("BG.png").await?;
-                let background_width = background.width() as i16;
-                let stone = load_image("Stone.png").await?;
-                let tiles_json = browser::fetch_json("tiles.json").await?;
-                let tiles = Rc::new(SpriteSheet::new(
-                    tiles_json.into_serde::<Sheet>()?,
-                    load_image("tiles.png").await?,
-                ));
-                let starting_obstacles = stone_and_platform(stone.clone(), tiles.clone(), 0);
-                let timeline = rightmost(&starting_obstacles);
-                let machine = WalkTheDogStateMachine::new(Walk {
-                    obstacle_sheet: tiles,
-                    boy: rhb,
-                    backgrounds: [
-                        Image::new(background.clone(), Point { x: 0, y: 0 }),
-                        Image::new(
-                            background.clone(),
-                            Point {
-                                x: background_width,
-                                y: 0,
-                            },
-                        ),
-                    ],
-                    obstacles: starting_obstacles,
-                    stone,
-                    timeline,
-                });
+                walk.play_music(assets.sound("music")?);
+                if editor::requested() {
+                    return Ok(Box::new(Editor::new(
+                        walk,
+                        assets.image("stone")?,
+                        assets.sprite_sheet("tiles")?,
+                    )));
+                }
+                let machine = WalkTheDogStateMachine::new(walk, Rc::new(LocalStore));
                 Ok(Box::new(WalkTheDog {
                     machine: Some(machine),
+                    last_keystate: KeyState::new(),
+                    assets: RefCell::new(AssetManager::new()),
+                    audio: RefCell::new(None),
                 }))
             }
             Some(_) => Err(anyhow!("Error: Game is already initialized!")),
         }
     }
 
-    fn update(&mut self, keystate: &KeyState) {
-        if let Some(machine) = self.machine.take() {
-            self.machine.replace(machine.update(keystate));
-        }
-        assert!(self.machine.is_some());
+    fn update(&mut self, keystate: &KeyState) -> Result<()> {
+        let machine = self
+            .machine
+            .take()
+            .ok_or_else(|| anyhow!("Error: Game is not initialized!"))?;
+        self.machine
+            .replace(machine.update(keystate, &self.last_keystate));
+        self.last_keystate = keystate.clone();
+        Ok(())
     }
 
-    fn draw(&self, renderer: &Renderer) {
+    fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
         renderer.clear(&Rect::new_from_x_y(0, 0, 600, 570));
-        if let Some(machine) = &self.machine {
-            machine.draw(renderer);
+        match &self.machine {
+            Some(machine) => machine.draw(renderer),
+            None => Ok(()),
+        }
+    }
+
+    fn draw_debug(&self, renderer: &dyn Renderer) -> Result<()> {
+        match &self.machine {
+            Some(machine) => machine.walk().draw_debug(renderer),
+            None => Ok(()),
         }
     }
 }
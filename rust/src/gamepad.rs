@@ -0,0 +1,102 @@
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+use crate::engine::KeyState;
+
+/// Gamepad buttons reach the action layer as key codes with this prefix, so
+/// they can be bound, recorded and replayed like keyboard keys.
+pub const GAMEPAD_PREFIX: &str = "Gamepad";
+/// How far a stick has to lean before it counts, as a fraction of full travel.
+pub const DEAD_ZONE: f64 = 0.25;
+
+// Indexed as in the W3C "standard" gamepad mapping.
+const BUTTON_CODES: [&str; 17] = [
+    "GamepadA",
+    "GamepadB",
+    "GamepadX",
+    "GamepadY",
+    "GamepadLB",
+    "GamepadRB",
+    "GamepadLT",
+    "GamepadRT",
+    "GamepadSelect",
+    "GamepadStart",
+    "GamepadLS",
+    "GamepadRS",
+    "GamepadUp",
+    "GamepadDown",
+    "GamepadLeft",
+    "GamepadRight",
+    "GamepadHome",
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GamepadSnapshot {
+    pub buttons: Vec<bool>,
+    pub axes: Vec<f64>,
+}
+
+impl GamepadSnapshot {
+    /// The codes held on this pad, sorted, with the left stick read as a d-pad.
+    pub fn pressed_codes(&self) -> Vec<&'static str> {
+        let mut codes: Vec<&'static str> = self
+            .buttons
+            .iter()
+            .zip(BUTTON_CODES)
+            .filter(|(pressed, _code)| **pressed)
+            .map(|(_pressed, code)| code)
+            .collect();
+        let x = self.axes.first().copied().unwrap_or_default();
+        let y = self.axes.get(1).copied().unwrap_or_default();
+        if x.hypot(y) >= DEAD_ZONE {
+            [
+                (x > DEAD_ZONE, "GamepadRight"),
+                (x < -DEAD_ZONE, "GamepadLeft"),
+                (y > DEAD_ZONE, "GamepadDown"),
+                (y < -DEAD_ZONE, "GamepadUp"),
+            ]
+            .into_iter()
+            .filter(|(leaning, _code)| *leaning)
+            .for_each(|(_leaning, code)| codes.push(code));
+        }
+        codes.sort_unstable();
+        codes.dedup();
+        codes
+    }
+}
+
+impl From<&Gamepad> for GamepadSnapshot {
+    fn from(gamepad: &Gamepad) -> Self {
+        GamepadSnapshot {
+            buttons: gamepad
+                .buttons()
+                .iter()
+                .map(|button| {
+                    button
+                        .dyn_into::<GamepadButton>()
+                        .is_ok_and(|button| button.pressed())
+                })
+                .collect(),
+            axes: gamepad
+                .axes()
+                .iter()
+                .map(|axis| axis.as_f64().unwrap_or_default())
+                .collect(),
+        }
+    }
+}
+
+/// Replaces the gamepad codes held in `keystate` with those of `gamepads`,
+/// leaving keyboard keys alone. Called once per frame, since pads are polled.
+pub fn merge(keystate: &mut KeyState, gamepads: &[GamepadSnapshot]) {
+    let released: Vec<String> = keystate
+        .pressed_keys()
+        .filter(|code| code.starts_with(GAMEPAD_PREFIX))
+        .map(str::to_string)
+        .collect();
+    released.iter().for_each(|code| keystate.set_released(code));
+    gamepads
+        .iter()
+        .flat_map(GamepadSnapshot::pressed_codes)
+        .for_each(|code| keystate.set_pressed(code));
+}
@@ -0,0 +1,147 @@
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::Store;
+
+const HIGH_SCORES_KEY: &str = "walk_the_dog.high_scores";
+const PLAYER_NAME_KEY: &str = "walk_the_dog.player_name";
+const DEFAULT_PLAYER_NAME: &str = "Player";
+const MAX_NAME_LENGTH: usize = 12;
+pub const HIGH_SCORE_CAPACITY: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HighScore {
+    pub name: String,
+    pub distance: u32,
+    pub score: u32,
+    pub seed: u32,
+    pub date: String,
+}
+
+pub struct HighScoreTable {
+    store: Rc<dyn Store>,
+    entries: Vec<HighScore>,
+    player_name: String,
+}
+
+impl HighScoreTable {
+    pub fn load(store: Rc<dyn Store>) -> Self {
+        let entries = store
+            .get::<Vec<HighScore>>(HIGH_SCORES_KEY)
+            .unwrap_or_else(|err| {
+                log!("Could not load high scores {:#?}", err);
+                None
+            })
+            .unwrap_or_default();
+        let player_name = store
+            .get::<String>(PLAYER_NAME_KEY)
+            .unwrap_or_else(|err| {
+                log!("Could not load player name {:#?}", err);
+                None
+            })
+            .unwrap_or_else(|| DEFAULT_PLAYER_NAME.to_string());
+        HighScoreTable {
+            store,
+            entries,
+            player_name,
+        }
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    pub fn player_name(&self) -> &str {
+        &self.player_name
+    }
+
+    pub fn qualifies(&self, distance: u32) -> bool {
+        self.entries.len() < HIGH_SCORE_CAPACITY
+            || self
+                .entries
+                .last()
+                .is_some_and(|lowest| distance > lowest.distance)
+    }
+
+    // Returns the zero-based rank of the run if it made the table.
+    pub fn submit(&mut self, distance: u32, score: u32, seed: u32, date: &str) -> Option<usize> {
+        if !self.qualifies(distance) {
+            return None;
+        }
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| distance > entry.distance)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(
+            rank,
+            HighScore {
+                name: self.player_name.clone(),
+                distance,
+                score,
+                seed,
+                date: date.to_string(),
+            },
+        );
+        self.entries.truncate(HIGH_SCORE_CAPACITY);
+        self.save();
+        Some(rank)
+    }
+
+    pub fn rename(&mut self, rank: usize, name: &str) {
+        let name: String = name.trim().chars().take(MAX_NAME_LENGTH).collect();
+        if name.is_empty() || name == self.player_name {
+            return;
+        }
+        if let Some(entry) = self.entries.get_mut(rank) {
+            entry.name = name.clone();
+        }
+        self.player_name = name;
+        self.save();
+    }
+
+    fn save(&self) {
+        if let Err(err) = self.store.set(HIGH_SCORES_KEY, &self.entries) {
+            log!("Could not save high scores {:#?}", err);
+        }
+        if let Err(err) = self.store.set(PLAYER_NAME_KEY, &self.player_name) {
+            log!("Could not save player name {:#?}", err);
+        }
+    }
+
+    pub fn to_html(&self, highlight: Option<usize>) -> String {
+        let rows: String = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(rank, entry)| {
+                format!(
+                    "<li{}>{} {} m {}</li>",
+                    if Some(rank) == highlight {
+                        " class='current'"
+                    } else {
+                        ""
+                    },
+                    escape_html(&entry.name),
+                    entry.distance,
+                    escape_html(&entry.date)
+                )
+            })
+            .collect();
+        format!("<ol id='high_scores'>{}</ol>", rows)
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '&' => "&amp;".to_string(),
+            '\'' => "&#39;".to_string(),
+            '"' => "&quot;".to_string(),
+            _ => c.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,285 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{KeyState, CLICK_PREFIX, FOCUS_LOST};
+use crate::gamepad::GAMEPAD_PREFIX;
+use crate::store::Store;
+use crate::touch::{SWIPE_DOWN, TAP, TOUCH_PREFIX};
+
+const KEY_BINDINGS_KEY: &str = "walk_the_dog.key_bindings";
+
+thread_local! {
+    static BINDINGS_OVERRIDE: RefCell<Option<KeyBindings>> = const { RefCell::new(None) };
+}
+
+/// Makes every following `KeyBindings::load` ignore the saved table, so a
+/// replay reads its keys the way they were bound when it was recorded.
+pub fn set_bindings(bindings: KeyBindings) {
+    BINDINGS_OVERRIDE.with(|bindings_override| bindings_override.replace(Some(bindings)));
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Run,
+    Jump,
+    Slide,
+    Pause,
+    Confirm,
+    Mute,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Run,
+        Action::Jump,
+        Action::Slide,
+        Action::Pause,
+        Action::Confirm,
+        Action::Mute,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Run => "Run",
+            Action::Jump => "Jump",
+            Action::Slide => "Slide",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Mute => "Mute",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Run => &["ArrowRight", "GamepadRight", TAP],
+            Action::Jump => &["Space", "GamepadA", TAP, "TouchJump"],
+            Action::Slide => &[
+                "ArrowDown",
+                "GamepadDown",
+                "GamepadB",
+                SWIPE_DOWN,
+                "TouchSlide",
+            ],
+            Action::Pause => &["Escape", "KeyP", "GamepadStart", "TouchPause"],
+            Action::Confirm => &["Enter", "GamepadA"],
+            Action::Mute => &["KeyM"],
+        }
+    }
+}
+
+// Keys are `KeyboardEvent.code` values, which name the physical key, so the
+// defaults sit in the same place on AZERTY and QWERTY keyboards alike.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    bindings: BTreeMap<Action, Vec<String>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| {
+                    (
+                        *action,
+                        action
+                            .default_keys()
+                            .iter()
+                            .map(|code| code.to_string())
+                            .collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn load(store: &dyn Store) -> Self {
+        if let Some(bindings) = BINDINGS_OVERRIDE.with(|bindings| bindings.borrow().clone()) {
+            return bindings;
+        }
+        store
+            .get::<KeyBindings>(KEY_BINDINGS_KEY)
+            .unwrap_or_else(|err| {
+                log!("Could not load key bindings {:#?}", err);
+                None
+            })
+            .map(KeyBindings::with_missing_defaults)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, store: &dyn Store) {
+        if let Err(err) = store.set(KEY_BINDINGS_KEY, self) {
+            log!("Could not save key bindings {:#?}", err);
+        }
+    }
+
+    // A table saved before an action or a device existed still gets the defaults
+    // for whatever it has nothing bound to, unless the player has since bound that
+    // key to another action.
+    fn with_missing_defaults(mut self) -> Self {
+        let rebound: BTreeSet<String> = self
+            .bindings
+            .iter()
+            .flat_map(|(action, keys)| {
+                keys.iter()
+                    .filter(|key| !action.default_keys().contains(&key.as_str()))
+            })
+            .cloned()
+            .collect();
+        KeyBindings::default()
+            .bindings
+            .into_iter()
+            .for_each(|(action, default_keys)| {
+                let keys = self.bindings.entry(action).or_default();
+                let missing: Vec<String> = default_keys
+                    .into_iter()
+                    .filter(|default_key| {
+                        !rebound.contains(default_key)
+                            && !keys
+                                .iter()
+                                .any(|key| Device::of(key) == Device::of(default_key))
+                    })
+                    .collect();
+                keys.extend(missing);
+            });
+        self
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == KeyBindings::default()
+    }
+
+    pub fn keys(&self, action: Action) -> &[String] {
+        self.bindings
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn labels(&self, action: Action) -> Vec<&str> {
+        self.keys(action)
+            .iter()
+            .filter(|code| Device::of(code).can_rebind())
+            .map(|code| key_label(code))
+            .collect()
+    }
+
+    /// Makes `code` the only key of its device for `action`, taking it away from
+    /// any other action. Rebinding a keyboard key leaves the gamepad and touch alone.
+    pub fn bind(&mut self, action: Action, code: &str) {
+        self.bindings
+            .values_mut()
+            .for_each(|keys| keys.retain(|key| key != code));
+        let keys = self.bindings.entry(action).or_default();
+        keys.retain(|key| Device::of(key) != Device::of(code));
+        keys.push(code.to_string());
+    }
+
+    pub fn actions(&self, keystate: &KeyState, last_keystate: &KeyState) -> Actions {
+        let just_pressed =
+            |code: &str| keystate.is_pressed(code) && !last_keystate.is_pressed(code);
+        let bound = |action: &Action, pressed: &dyn Fn(&str) -> bool| {
+            self.keys(*action).iter().any(|code| pressed(code))
+        };
+        Actions {
+            held: Action::ALL
+                .iter()
+                .filter(|action| bound(action, &|code| keystate.is_pressed(code)))
+                .copied()
+                .collect(),
+            pressed: Action::ALL
+                .iter()
+                .filter(|action| bound(action, &just_pressed))
+                .copied()
+                .collect(),
+            pressed_key: keystate
+                .pressed_keys()
+                .filter(|code| Device::of(code).can_rebind() && just_pressed(code))
+                .min()
+                .map(str::to_string),
+            focus_lost: just_pressed(FOCUS_LOST),
+            clicked: keystate
+                .pressed_keys()
+                .filter_map(|code| code.strip_prefix(CLICK_PREFIX))
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Device {
+    Keyboard,
+    Gamepad,
+    Touch,
+    Window,
+    Button,
+}
+
+impl Device {
+    fn of(code: &str) -> Self {
+        if code == FOCUS_LOST {
+            Device::Window
+        } else if code.starts_with(CLICK_PREFIX) {
+            Device::Button
+        } else if code.starts_with(GAMEPAD_PREFIX) {
+            Device::Gamepad
+        } else if code.starts_with(TOUCH_PREFIX) {
+            Device::Touch
+        } else {
+            Device::Keyboard
+        }
+    }
+
+    // Touch gestures have no key to press on the controls screen.
+    fn can_rebind(&self) -> bool {
+        matches!(self, Device::Keyboard | Device::Gamepad)
+    }
+}
+
+/// A short name for a key code, e.g. `P` for `KeyP` or `Start` for `GamepadStart`.
+pub fn key_label(code: &str) -> &str {
+    ["Key", "Digit", GAMEPAD_PREFIX]
+        .iter()
+        .find_map(|prefix| code.strip_prefix(prefix))
+        .unwrap_or(code)
+}
+
+/// What the player asked for on one tick, once keys are looked up in the bindings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Actions {
+    held: BTreeSet<Action>,
+    pressed: BTreeSet<Action>,
+    pressed_key: Option<String>,
+    focus_lost: bool,
+    clicked: BTreeSet<String>,
+}
+
+impl Actions {
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    /// True only on the tick the action's key went down.
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// The raw key that went down this tick, for capturing a new binding.
+    pub fn pressed_key(&self) -> Option<&str> {
+        self.pressed_key.as_deref()
+    }
+
+    pub fn focus_lost(&self) -> bool {
+        self.focus_lost
+    }
+
+    /// True on the tick the UI button `id` was clicked.
+    pub fn clicked(&self, id: &str) -> bool {
+        self.clicked.contains(id)
+    }
+}
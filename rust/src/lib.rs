@@ -1,16 +1,35 @@
 use engine::GameLoop;
 use game::WalkTheDog;
+use replay::InputMode;
 use wasm_bindgen::prelude::*;
 
 #[macro_use]
-mod browser;
-mod engine;
-mod game;
-mod red_hat_boy;
-mod red_hat_boy_states;
-mod segments;
-mod obstacles;
-mod sound;
+pub mod browser;
+pub mod animation;
+pub mod aseprite;
+pub mod assets;
+pub mod benchmark;
+pub mod debug;
+pub mod difficulty;
+pub mod editor;
+pub mod engine;
+pub mod error_scene;
+pub mod game;
+pub mod gamepad;
+pub mod high_scores;
+pub mod input;
+pub mod loading;
+pub mod mixer;
+pub mod red_hat_boy;
+pub mod red_hat_boy_states;
+pub mod replay;
+pub mod score;
+pub mod segments;
+pub mod obstacles;
+pub mod parallax;
+pub mod sound;
+pub mod store;
+pub mod touch;
 
 // This is like the `main` function, except for JavaScript.
 #[wasm_bindgen(start)]
@@ -18,7 +37,34 @@ pub fn main_js() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
     browser::spawn_local(async move {
         let game = WalkTheDog::new();
-        GameLoop::start(game).await.expect("Coult not start game loop");
+        let input = match InputMode::from_query().await {
+            Ok(input) => input,
+            Err(err) => {
+                log!("Could not load the input recording {:#}", err);
+                return;
+            }
+        };
+        if let Err(err) = GameLoop::start(game, input).await {
+            log!("Could not start the game {:#}", err);
+        }
     });
     Ok(())
 }
+
+// Lets the page pin the obstacle course, e.g. to race the TypeScript build on the same seed.
+#[wasm_bindgen]
+pub fn set_seed(seed: u32) {
+    game::set_seed(seed);
+}
+
+// Returns the session captured with `?record` as JSON, ready to be served back through `?replay=`.
+#[wasm_bindgen]
+pub fn export_recording() -> Option<String> {
+    replay::active_recording().and_then(|recording| recording.to_json().ok())
+}
+
+// The report of the last `?benchmark` run, in the JSON schema shared with the TypeScript build.
+#[wasm_bindgen]
+pub fn benchmark_report() -> Option<String> {
+    benchmark::last_report().map(|report| report.to_json())
+}
@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{Error, Result};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver};
+
+use crate::assets::{AssetErrors, LoadProgress};
+use crate::browser;
+use crate::engine::{self, Game, Point, Rect, Renderer, TextAlign, TextStyle};
+use crate::high_scores::escape_html;
+
+const BACKGROUND: &str = "#1d2b3a";
+const BAR: Rect = Rect::new_from_x_y(100, 270, 400, 24);
+const BAR_TRACK_COLOR: &str = "rgba(255, 255, 255, 0.25)";
+const BAR_COLOR: &str = "white";
+const TITLE_STYLE: TextStyle = TextStyle {
+    font: "40px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Center,
+};
+const STATUS_STYLE: TextStyle = TextStyle {
+    font: "18px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Center,
+};
+
+/// Drawn every frame until the game is initialized, and behind the error UI
+/// if it can't be.
+pub fn draw_loading_scene(
+    renderer: &dyn Renderer,
+    progress: &LoadProgress,
+    failed: bool,
+) -> Result<()> {
+    renderer.fill_rect(&Rect::new_from_x_y(0, 0, 600, 570), BACKGROUND);
+    renderer.draw_text("Walk the Dog", &Point { x: 300, y: 230 }, &TITLE_STYLE)?;
+    renderer.fill_rect(&BAR, BAR_TRACK_COLOR);
+    let filled = (BAR.width as f32 * progress.fraction()).round() as i32;
+    renderer.fill_rect(
+        &Rect::new_from_x_y(BAR.x(), BAR.y(), filled, BAR.height),
+        BAR_COLOR,
+    );
+    let status = if failed {
+        "Loading failed".to_string()
+    } else {
+        match progress.current() {
+            Some(current) => format!("Loading {}", current),
+            None => "Loading".to_string(),
+        }
+    };
+    renderer.draw_text(&status, &Point { x: 300, y: 330 }, &STATUS_STYLE)
+}
+
+/// Lists each asset that failed when the error came from the asset manager.
+pub fn load_error_ui(err: &Error) -> String {
+    let details: String = match err.downcast_ref::<AssetErrors>() {
+        Some(AssetErrors(errors)) => errors
+            .iter()
+            .map(|error| {
+                format!(
+                    "<li>{} ({}): {}</li>",
+                    escape_html(&error.key),
+                    escape_html(&error.source),
+                    escape_html(&error.message)
+                )
+            })
+            .collect(),
+        None => format!("<li>{}</li>", escape_html(&format!("{:#}", err))),
+    };
+    format!(
+        "<div id='load_error'><p>Could not load the game</p><ul>{}</ul><button id='retry'>Retry</button></div>",
+        details
+    )
+}
+
+type LoadResult = Rc<RefCell<Option<Result<Box<dyn Game>>>>>;
+
+/// Runs `Game::initialize` in the background and keeps retrying it on request.
+pub struct Loader {
+    game: Rc<dyn Game>,
+    progress: LoadProgress,
+    result: LoadResult,
+    retry_event: Option<UnboundedReceiver<()>>,
+}
+
+impl Loader {
+    pub fn start(game: Rc<dyn Game>) -> Self {
+        let loader = Loader {
+            game,
+            progress: LoadProgress::new(),
+            result: Rc::new(RefCell::new(None)),
+            retry_event: None,
+        };
+        loader.spawn();
+        loader
+    }
+
+    fn spawn(&self) {
+        let game = self.game.clone();
+        let progress = self.progress.clone();
+        let result = self.result.clone();
+        browser::spawn_local(async move {
+            let initialized = game.initialize(&progress).await;
+            result.replace(Some(initialized));
+        });
+    }
+
+    /// Returns the initialized game once it's ready.
+    pub fn poll(&mut self) -> Option<Box<dyn Game>> {
+        if let Some(retry_event) = &mut self.retry_event {
+            if matches!(retry_event.try_next(), Ok(Some(()))) {
+                self.retry_event = None;
+                if let Err(err) = browser::hide_ui() {
+                    log!("Error hiding the load error UI {:#?}", err);
+                }
+                self.spawn();
+            }
+            return None;
+        }
+        let result = self.result.borrow_mut().take()?;
+        match result {
+            Ok(game) => Some(game),
+            Err(err) => {
+                log!("Could not load the game {:#}", err);
+                self.retry_event = Some(
+                    browser::draw_ui(&load_error_ui(&err))
+                        .and_then(|_unit| browser::find_html_element_by_id("retry"))
+                        .map(engine::add_click_handler)
+                        .unwrap_or_else(|err| {
+                            log!("Could not show the load error UI {:#?}", err);
+                            unbounded().1
+                        }),
+                );
+                None
+            }
+        }
+    }
+
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        draw_loading_scene(renderer, &self.progress, self.retry_event.is_some())
+    }
+}
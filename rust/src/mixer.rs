@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+use crate::store::Store;
+
+const MIXER_KEY: &str = "walk_the_dog.mixer";
+pub const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 3] = [Bus::Master, Bus::Music, Bus::Sfx];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Bus::Master => "Volume",
+            Bus::Music => "Music",
+            Bus::Sfx => "Sound effects",
+        }
+    }
+}
+
+/// Volumes run from 0 to 1. Muting silences the master bus but keeps its
+/// volume for when the sound comes back.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct MixerSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    pub muted: bool,
+}
+
+impl Default for MixerSettings {
+    fn default() -> Self {
+        MixerSettings {
+            master: 1.0,
+            music: 0.6,
+            sfx: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl MixerSettings {
+    pub fn load(store: &dyn Store) -> Self {
+        store
+            .get::<MixerSettings>(MIXER_KEY)
+            .unwrap_or_else(|err| {
+                log!("Could not load the mixer settings {:#?}", err);
+                None
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, store: &dyn Store) {
+        if let Err(err) = store.set(MIXER_KEY, self) {
+            log!("Could not save the mixer settings {:#?}", err);
+        }
+    }
+
+    pub fn volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master => self.master,
+            Bus::Music => self.music,
+            Bus::Sfx => self.sfx,
+        }
+    }
+
+    pub fn set_volume(&mut self, bus: Bus, volume: f32) {
+        let volume = (volume.clamp(0.0, 1.0) / VOLUME_STEP).round() * VOLUME_STEP;
+        match bus {
+            Bus::Master => self.master = volume,
+            Bus::Music => self.music = volume,
+            Bus::Sfx => self.sfx = volume,
+        }
+    }
+
+    /// What the bus's gain node should be set to.
+    pub fn gain(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master if self.muted => 0.0,
+            _ => self.volume(bus),
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use std::rc::Rc;
 
+use anyhow::Result;
+
 use crate::{engine::{Cell, Renderer, SpriteSheet, Rect, Point, Image}, red_hat_boy::RedHatBoy};
 
-pub const LOW_PLATFORM: i16 = 420;
-pub const HIGH_PLATFORM: i16 = 375;
-pub const FIRST_PLATFORM: i16 = 200;
+pub const LOW_PLATFORM: i32 = 420;
+pub const HIGH_PLATFORM: i32 = 375;
+pub const FIRST_PLATFORM: i32 = 200;
 
 pub const FLOATING_PLATFORM_SPRITES: [&str; 3] = ["13.png", "14.png", "15.png"];
 pub const FLOATING_PLATFORM_BOUNDING_BOXES: [Rect; 3] = [
@@ -15,9 +17,9 @@ pub const FLOATING_PLATFORM_BOUNDING_BOXES: [Rect; 3] = [
 
 pub trait Obstacle {
 	fn check_intersection(&self, boy: &mut RedHatBoy);
-	fn draw(&self, renderer: &Renderer);
-	fn move_horizontally(&mut self, x: i16);
-	fn right(&self) -> i16;
+	fn draw(&self, renderer: &dyn Renderer) -> Result<()>;
+	fn right(&self) -> i32;
+	fn bounding_boxes(&self) -> Vec<Rect>;
 }
 
 pub struct Platform {
@@ -56,45 +58,28 @@ impl Platform {
 					bounding_boxes,
 			}
 	}
-
-	fn bounding_boxes(&self) -> &Vec<Rect> {
-			&self.bounding_boxes
-	}
 }
 
 impl Obstacle for Platform {
-	fn draw(&self, renderer: &Renderer) {
+	fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
 			let mut x = 0;
-			self.sprites.iter().for_each(|sprite| {
+			self.sprites.iter().try_for_each(|sprite| {
 					self.sheet.draw(
 							renderer,
-							&Rect::new_from_x_y(
-									sprite.frame.x,
-									sprite.frame.y,
-									sprite.frame.w,
-									sprite.frame.h,
-							),
-							&Rect::new_from_x_y(
-									self.position.x + x,
-									self.position.y,
-									sprite.frame.w,
-									sprite.frame.h,
-							),
-					);
-					x += sprite.frame.w;
-			});
-	}
-
-	fn move_horizontally(&mut self, x: i16) {
-			self.position.x += x;
-			self.bounding_boxes.iter_mut().for_each(|bounding_box| {
-					bounding_box.set_x(bounding_box.x() + x);
+							sprite,
+							&Point {
+									x: self.position.x + x,
+									y: self.position.y,
+							},
+					)?;
+					x += self.sheet.source_size(sprite).w;
+					Ok(())
 			})
 	}
 
 	fn check_intersection(&self, boy: &mut RedHatBoy) {
 			if let Some(box_to_land_on) = self
-					.bounding_boxes()
+					.bounding_boxes
 					.iter()
 					.find(|&bounding_box| boy.bounding_box().intersects(bounding_box))
 			{
@@ -106,12 +91,16 @@ impl Obstacle for Platform {
 			}
 	}
 
-	fn right(&self) -> i16 {
-			self.bounding_boxes()
+	fn right(&self) -> i32 {
+			self.bounding_boxes
 					.last()
 					.unwrap_or(&Rect::default())
 					.right()
 	}
+
+	fn bounding_boxes(&self) -> Vec<Rect> {
+			self.bounding_boxes.clone()
+	}
 }
 
 pub struct Barrier {
@@ -131,16 +120,16 @@ impl Obstacle for Barrier {
 			}
 	}
 
-	fn draw(&self, renderer: &Renderer) {
-			self.image.draw(renderer);
+	fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+			self.image.draw(renderer)
 	}
 
-	fn move_horizontally(&mut self, x: i16) {
-			self.image.move_horizontally(x);
+	fn right(&self) -> i32 {
+			self.image.right()
 	}
 
-	fn right(&self) -> i16 {
-			self.image.right()
+	fn bounding_boxes(&self) -> Vec<Rect> {
+			vec![self.image.bounding_box().clone()]
 	}
 }
 
@@ -0,0 +1,182 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::engine::{Camera, ImageHandle, Point, Renderer};
+
+const VIEW_WIDTH: i32 = 600;
+
+fn default_scroll_factor() -> f32 {
+    1.0
+}
+
+fn default_tile() -> bool {
+    true
+}
+
+/// One layer of the background, drawn in the order they are listed.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct LayerDefinition {
+    /// An image key from the asset manifest.
+    pub image: String,
+    /// How far the layer moves for each pixel the ground does. 1 keeps up with
+    /// the ground, 0 stays put.
+    #[serde(default = "default_scroll_factor")]
+    pub scroll_factor: f32,
+    #[serde(default)]
+    pub y: i32,
+    /// Repeats the image across the screen rather than drawing it once.
+    #[serde(default = "default_tile")]
+    pub tile: bool,
+    /// Drawn over the boy and the obstacles instead of behind them.
+    #[serde(default)]
+    pub foreground: bool,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct BackgroundFile {
+    pub layers: Vec<LayerDefinition>,
+}
+
+impl BackgroundFile {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|err| anyhow!("Could not parse background {:#?}", err))
+    }
+}
+
+pub struct ParallaxLayer {
+    image: ImageHandle,
+    scroll_factor: f32,
+    y: i32,
+    tile: bool,
+    foreground: bool,
+}
+
+impl ParallaxLayer {
+    pub fn new(image: ImageHandle, definition: &LayerDefinition) -> Self {
+        ParallaxLayer {
+            image,
+            scroll_factor: definition.scroll_factor,
+            y: definition.y,
+            tile: definition.tile,
+            foreground: definition.foreground,
+        }
+    }
+
+    /// Where the layer is drawn on screen with the camera at `camera`. A tiled
+    /// layer looks the same a whole image further on, so it is kept within one.
+    pub fn position(&self, camera: &Camera) -> Point {
+        let view = camera.view_position();
+        let scrolled =
+            |distance: i32| (f64::from(distance) * f64::from(self.scroll_factor)).round() as i32;
+        let x = -scrolled(view.x);
+        let y = self.y - scrolled(view.y);
+        let width = self.image.width();
+        if self.tile && width > 0 {
+            Point {
+                x: x.rem_euclid(width),
+                y,
+            }
+        } else {
+            Point { x, y }
+        }
+    }
+
+    pub fn draw(&self, renderer: &dyn Renderer, camera: &Camera) -> Result<()> {
+        let position = self.position(camera);
+        let width = self.image.width();
+        if !self.tile || width <= 0 {
+            return renderer.draw_entire_image(&self.image, &position);
+        }
+        let first = if position.x > 0 {
+            position.x - width
+        } else {
+            position.x
+        };
+        (0..)
+            .map(|tile| first + tile * width)
+            .take_while(|&tile_x| tile_x < VIEW_WIDTH)
+            .try_for_each(|tile_x| {
+                renderer.draw_entire_image(
+                    &self.image,
+                    &Point {
+                        x: tile_x,
+                        y: position.y,
+                    },
+                )
+            })
+    }
+}
+
+/// The scenery behind the walk, and in front of it, checked against the
+/// loaded images like the segments are. Layers never move themselves; each is
+/// drawn from where the camera is, at its own fraction of the camera's speed.
+pub struct Background {
+    layers: Vec<ParallaxLayer>,
+}
+
+impl Background {
+    pub fn new(file: &BackgroundFile, image: impl Fn(&str) -> Result<ImageHandle>) -> Result<Self> {
+        let mut problems = vec![];
+        if file.layers.is_empty() {
+            problems.push("there are no layers".to_string());
+        }
+        let layers = file
+            .layers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, definition)| {
+                if !definition.scroll_factor.is_finite() || definition.scroll_factor < 0.0 {
+                    problems.push(format!(
+                        "layer {}: the scroll factor must be 0 or more",
+                        index + 1
+                    ));
+                }
+                match image(&definition.image) {
+                    Ok(handle) => Some(ParallaxLayer::new(handle, definition)),
+                    Err(err) => {
+                        problems.push(format!("layer {}: {:#}", index + 1, err));
+                        None
+                    }
+                }
+            })
+            .collect();
+        if !problems.is_empty() {
+            return Err(anyhow!("Invalid background: {}", problems.join("; ")));
+        }
+        Ok(Background { layers })
+    }
+
+    /// A single tiled image that keeps up with the ground.
+    pub fn from_image(image: ImageHandle) -> Self {
+        Background {
+            layers: vec![ParallaxLayer::new(
+                image,
+                &LayerDefinition {
+                    image: String::new(),
+                    scroll_factor: 1.0,
+                    y: 0,
+                    tile: true,
+                    foreground: false,
+                },
+            )],
+        }
+    }
+
+    pub fn layers(&self) -> &[ParallaxLayer] {
+        &self.layers
+    }
+
+    pub fn draw_behind(&self, renderer: &dyn Renderer, camera: &Camera) -> Result<()> {
+        self.layers
+            .iter()
+            .filter(|layer| !layer.foreground)
+            .try_for_each(|layer| layer.draw(renderer, camera))
+    }
+
+    pub fn draw_in_front(&self, renderer: &dyn Renderer, camera: &Camera) -> Result<()> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.foreground)
+            .try_for_each(|layer| layer.draw(renderer, camera))
+    }
+}
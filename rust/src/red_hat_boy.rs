@@ -1,7 +1,12 @@
-use web_sys::HtmlImageElement;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use anyhow::Result;
 
 use crate::{
-    engine::{Audio, Cell, Rect, Renderer, Sheet, Sound},
+    animation::Animations,
+    engine::{Audio, Cell, ImageHandle, Point, Rect, Renderer, Sheet, Sound, SpriteSheet},
     red_hat_boy_states::{
         Falling, FallingEndState, Idle, Jumping, JumpingEndState, KnockedOut, RedHatBoyContext,
         RedHatBoyState, Running, Sliding, SlidingEndState,
@@ -14,7 +19,7 @@ pub enum Event {
     Update,
     Jump,
     KnockOut,
-    Land(i16),
+    Land(i32),
 }
 
 #[derive(Clone)]
@@ -54,25 +59,25 @@ impl RedHatBoyStateMachine {
         }
     }
 
-    fn frame_name(&self) -> &str {
+    fn clip(&self) -> &str {
         match self {
-            RedHatBoyStateMachine::Idle(state) => state.frame_name(),
-            RedHatBoyStateMachine::Running(state) => state.frame_name(),
-            RedHatBoyStateMachine::Sliding(state) => state.frame_name(),
-            RedHatBoyStateMachine::Jumping(state) => state.frame_name(),
-            RedHatBoyStateMachine::Falling(state) => state.frame_name(),
-            RedHatBoyStateMachine::KnockedOut(state) => state.frame_name(),
+            RedHatBoyStateMachine::Idle(state) => state.clip(),
+            RedHatBoyStateMachine::Running(state) => state.clip(),
+            RedHatBoyStateMachine::Sliding(state) => state.clip(),
+            RedHatBoyStateMachine::Jumping(state) => state.clip(),
+            RedHatBoyStateMachine::Falling(state) => state.clip(),
+            RedHatBoyStateMachine::KnockedOut(state) => state.clip(),
         }
     }
 
     fn context(&self) -> &RedHatBoyContext {
         match self {
-            RedHatBoyStateMachine::Idle(state) => &state.context(),
-            RedHatBoyStateMachine::Running(state) => &state.context(),
-            RedHatBoyStateMachine::Sliding(state) => &state.context(),
-            RedHatBoyStateMachine::Jumping(state) => &state.context(),
-            RedHatBoyStateMachine::Falling(state) => &state.context(),
-            RedHatBoyStateMachine::KnockedOut(state) => &state.context(),
+            RedHatBoyStateMachine::Idle(state) => state.context(),
+            RedHatBoyStateMachine::Running(state) => state.context(),
+            RedHatBoyStateMachine::Sliding(state) => state.context(),
+            RedHatBoyStateMachine::Jumping(state) => state.context(),
+            RedHatBoyStateMachine::Falling(state) => state.context(),
+            RedHatBoyStateMachine::KnockedOut(state) => state.context(),
         }
     }
 
@@ -80,9 +85,36 @@ impl RedHatBoyStateMachine {
         self.transition(Event::Update)
     }
 
+    // Moving forward doesn't change what the boy is doing, so it isn't an event.
+    fn travel(self, distance: i32) -> Self {
+        match self {
+            RedHatBoyStateMachine::Idle(state) => state.travel(distance).into(),
+            RedHatBoyStateMachine::Running(state) => state.travel(distance).into(),
+            RedHatBoyStateMachine::Sliding(state) => state.travel(distance).into(),
+            RedHatBoyStateMachine::Jumping(state) => state.travel(distance).into(),
+            RedHatBoyStateMachine::Falling(state) => state.travel(distance).into(),
+            RedHatBoyStateMachine::KnockedOut(state) => state.travel(distance).into(),
+        }
+    }
+
+    fn falling(&self) -> bool {
+        matches!(self, RedHatBoyStateMachine::Falling(_))
+    }
+
     fn knocked_out(&self) -> bool {
         matches!(self, RedHatBoyStateMachine::KnockedOut(_))
     }
+
+    fn name(&self) -> &'static str {
+        match self {
+            RedHatBoyStateMachine::Idle(_) => "Idle",
+            RedHatBoyStateMachine::Running(_) => "Running",
+            RedHatBoyStateMachine::Sliding(_) => "Sliding",
+            RedHatBoyStateMachine::Jumping(_) => "Jumping",
+            RedHatBoyStateMachine::Falling(_) => "Falling",
+            RedHatBoyStateMachine::KnockedOut(_) => "KnockedOut",
+        }
+    }
 }
 
 impl From<RedHatBoyState<Idle>> for RedHatBoyStateMachine {
@@ -148,62 +180,92 @@ impl From<RedHatBoyState<KnockedOut>> for RedHatBoyStateMachine {
     }
 }
 
+/// The slice that marks a frame's collision box, in sheets that have one.
+pub const HITBOX_SLICE: &str = "hitbox";
+
 pub struct RedHatBoy {
     state_machine: RedHatBoyStateMachine,
-    sprite_sheet: Sheet,
-    image: HtmlImageElement,
+    sprite_sheet: SpriteSheet,
+    // Frames the sheet doesn't have, so each is only logged the first time.
+    missing_frames: RefCell<BTreeSet<String>>,
 }
 
 impl RedHatBoy {
-    pub fn new(sheet: Sheet, image: HtmlImageElement, audio: Audio, jump_sound: Sound) -> Self {
+    pub fn new(sheet: Sheet, image: ImageHandle, audio: Audio, jump_sound: Sound) -> Self {
+        let animations = Rc::new(Animations::from_sheet(&sheet));
         RedHatBoy {
-            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new(audio, jump_sound)),
-            sprite_sheet: sheet,
-            image,
+            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new(
+                audio, jump_sound, animations,
+            )),
+            sprite_sheet: SpriteSheet::new(sheet, image),
+            missing_frames: RefCell::new(BTreeSet::new()),
         }
     }
 
-    fn frame_name(&self) -> String {
-        format!(
-            "{} ({}).png",
-            self.state_machine.frame_name(),
-            (self.state_machine.context().frame / 3) + 1
-        )
+    // The clip's name stands in for the frame when the sheet has no such clip.
+    fn frame_name(&self) -> &str {
+        let context = self.state_machine.context();
+        let clip = self.state_machine.clip();
+        context
+            .animations
+            .clip(clip)
+            .and_then(|animation| animation.frame_at(context.frame))
+            .unwrap_or(clip)
     }
 
     fn current_sprite(&self) -> Option<&Cell> {
-        self.sprite_sheet.frames.get(&self.frame_name())
+        let frame_name = self.frame_name();
+        let sprite = self.sprite_sheet.cell(frame_name);
+        if sprite.is_none()
+            && self
+                .missing_frames
+                .borrow_mut()
+                .insert(frame_name.to_string())
+        {
+            log!("The sprite sheet has no cell named {}", frame_name);
+        }
+        sprite
+    }
+
+    pub fn audio(&self) -> &Audio {
+        &self.state_machine.context().audio
+    }
+
+    pub fn missing_frames(&self) -> Vec<String> {
+        self.missing_frames.borrow().iter().cloned().collect()
     }
 
-    pub fn draw(&self, renderer: &Renderer) {
-        let sprite = self.current_sprite().expect("Cell not found");
-        renderer.draw_image(
-            &self.image,
-            &Rect::new_from_x_y(
-                sprite.frame.x.into(),
-                sprite.frame.y.into(),
-                sprite.frame.w.into(),
-                sprite.frame.h.into(),
-            ),
-            &self.destination_box(),
-        );
+    // A frame missing from the sheet is skipped rather than taking the game down.
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        match self.current_sprite() {
+            Some(sprite) => {
+                self.sprite_sheet
+                    .draw(renderer, sprite, &self.state_machine.context().position)
+            }
+            None => Ok(()),
+        }
     }
 
-    fn destination_box(&self) -> Rect {
-        let sprite = self.current_sprite().expect("Cell not found");
-        Rect::new_from_x_y(
-            (self.state_machine.context().position.x + sprite.sprite_source_size.x).into(),
-            (self.state_machine.context().position.y + sprite.sprite_source_size.y).into(),
-            sprite.frame.w.into(),
-            sprite.frame.h.into(),
-        )
+    fn destination_box(&self, sprite: &Cell) -> Rect {
+        self.sprite_sheet
+            .destination(sprite, &self.state_machine.context().position)
     }
 
     pub fn bounding_box(&self) -> Rect {
-        const X_OFFSET: i16 = 18;
-        const Y_OFFSET: i16 = 14;
-        const WIDTH_OFFSET: i16 = 28;
-        let mut bounding_box = self.destination_box();
+        const X_OFFSET: i32 = 18;
+        const Y_OFFSET: i32 = 14;
+        const WIDTH_OFFSET: i32 = 28;
+        let Some(sprite) = self.current_sprite() else {
+            return Rect::new(self.state_machine.context().position, 0, 0);
+        };
+        // A sheet with hitboxes drawn in says exactly where the boy can be hit.
+        if let Some(hitbox) =
+            self.sprite_sheet
+                .slice(sprite, HITBOX_SLICE, &self.state_machine.context().position)
+        {
+            return hitbox;
+        }
+        let mut bounding_box = self.destination_box(sprite);
         bounding_box.set_x(bounding_box.x() + X_OFFSET);
         bounding_box.width -= WIDTH_OFFSET;
         bounding_box.set_y(bounding_box.y() + Y_OFFSET);
@@ -227,7 +289,7 @@ impl RedHatBoy {
         self.state_machine = self.state_machine.clone().transition(Event::Jump);
     }
 
-    pub fn land_on(&mut self, position: i16) {
+    pub fn land_on(&mut self, position: i32) {
         self.state_machine = self.state_machine.clone().transition(Event::Land(position));
     }
 
@@ -235,28 +297,58 @@ impl RedHatBoy {
         self.state_machine = self.state_machine.clone().transition(Event::KnockOut);
     }
 
-    pub fn pos_y(&self) -> i16 {
+    /// Moves the boy `distance` pixels on through the world.
+    pub fn travel(&mut self, distance: i32) {
+        self.state_machine = self.state_machine.clone().travel(distance);
+    }
+
+    pub fn position(&self) -> Point {
+        self.state_machine.context().position
+    }
+
+    pub fn pos_x(&self) -> i32 {
+        self.state_machine.context().position.x
+    }
+
+    pub fn pos_y(&self) -> i32 {
         self.state_machine.context().position.y
     }
 
-    pub fn velocity_y(&self) -> i16 {
+    pub fn velocity_y(&self) -> i32 {
         self.state_machine.context().velocity.y
     }
 
-    pub fn walking_speed(&self) -> i16 {
+    pub fn walking_speed(&self) -> i32 {
         self.state_machine.context().velocity.x
     }
 
+    /// Hit something and on the way down, before lying knocked out.
+    pub fn falling(&self) -> bool {
+        self.state_machine.falling()
+    }
+
     pub fn knocked_out(&self) -> bool {
         self.state_machine.knocked_out()
     }
 
+    pub fn state_name(&self) -> &'static str {
+        self.state_machine.name()
+    }
+
+    pub fn frame(&self) -> u16 {
+        self.state_machine.context().frame
+    }
+
     pub fn reset(boy: Self) -> Self {
-        RedHatBoy::new(
-            boy.sprite_sheet,
-            boy.image,
-            boy.state_machine.context().audio.clone(),
-            boy.state_machine.context().jump_sound.clone(),
-        )
+        let context = boy.state_machine.context();
+        RedHatBoy {
+            state_machine: RedHatBoyStateMachine::Idle(RedHatBoyState::new(
+                context.audio.clone(),
+                context.jump_sound.clone(),
+                context.animations.clone(),
+            )),
+            sprite_sheet: boy.sprite_sheet,
+            missing_frames: boy.missing_frames,
+        }
     }
 }
@@ -1,29 +1,25 @@
-use js_sys::Math::log;
+use std::rc::Rc;
 
-use crate::{engine::{Point, Audio, Sound}, sound::LOOPING};
+use crate::{animation::{AnimationEvent, Animations}, engine::{Point, Audio, Sound}, sound::Looping};
 
-const FLOOR: i16 = 449;
-const HEIGHT: i16 = 570;
-const PLAYER_HEIGHT: i16 = HEIGHT - FLOOR;
-const STARTING_POINT: i16 = -20;
+const FLOOR: i32 = 449;
+const HEIGHT: i32 = 570;
+const PLAYER_HEIGHT: i32 = HEIGHT - FLOOR;
+const STARTING_POINT: i32 = -20;
 
-const IDLE_FRAME_NAME: &str = "Idle";
-const RUN_FRAME_NAME: &str = "Run";
-const SLIDING_FRAME_NAME: &str = "Slide";
-const JUMPING_FRAME_NAME: &str = "Jump";
-const FALLING_FRAME_NAME: &str = "Dead";
+// Clip names in the sheet. How many frames each has, and how they play,
+// comes from the sheet itself.
+const IDLE_CLIP: &str = "Idle";
+const RUN_CLIP: &str = "Run";
+const SLIDING_CLIP: &str = "Slide";
+const JUMPING_CLIP: &str = "Jump";
+const FALLING_CLIP: &str = "Dead";
 
-const IDLE_FRAMES: u8 = 29;
-const RUNNING_FRAMES: u8 = 23;
-pub const SLIDING_FRAMES: u8 = 14;
-pub const JUMPING_FRAMES: u8 = 35;
-pub const FALLING_FRAMES: u8 = 29;
+const RUNNING_SPEED: i32 = 4;
+const JUMP_SPEED: i32 = -25;
 
-const RUNNING_SPEED: i16 = 4;
-const JUMP_SPEED: i16 = -25;
-
-const GRAVITY: i16 = 1;
-const TERMINAL_VELOCITY: i16 = 20;
+const GRAVITY: i32 = 1;
+const TERMINAL_VELOCITY: i32 = 20;
 
 #[derive(Clone)]
 pub struct RedHatBoyState<S> {
@@ -33,20 +29,30 @@ pub struct RedHatBoyState<S> {
 
 #[derive(Clone)]
 pub struct RedHatBoyContext {
-    pub frame: u8,
+    pub frame: u16,
     pub position: Point,
     pub velocity: Point,
     pub audio: Audio,
     pub jump_sound: Sound,
+    pub animations: Rc<Animations>,
 }
 
 impl RedHatBoyContext {
-    pub fn update(mut self, frame_count: u8) -> Self {
-        if self.frame < frame_count {
-            self.frame += 1;
-        } else {
-            self.frame = 0;
-        }
+    pub fn update(self, clip: &str) -> Self {
+        self.advance(clip).0
+    }
+
+    // A clip the sheet doesn't have counts as finished straight away, so the
+    // boy still gets up from a slide.
+    fn advance(mut self, clip: &str) -> (Self, Option<AnimationEvent>) {
+        let event = match self.animations.clip(clip) {
+            Some(clip) => {
+                let (frame, event) = clip.advance(self.frame);
+                self.frame = frame;
+                event
+            }
+            None => Some(AnimationEvent::Completed),
+        };
         if self.velocity.y < TERMINAL_VELOCITY {
             self.velocity.y += GRAVITY;
         }
@@ -54,6 +60,11 @@ impl RedHatBoyContext {
         if self.position.y > FLOOR {
             self.position.y = FLOOR;
         }
+        (self, event)
+    }
+
+    fn travel(mut self, distance: i32) -> Self {
+        self.position.x += distance;
         self
     }
 
@@ -67,7 +78,7 @@ impl RedHatBoyContext {
         self
     }
 
-    fn set_vertical_velocity(mut self, y: i16) -> Self {
+    fn set_vertical_velocity(mut self, y: i32) -> Self {
         self.velocity.y = y;
         self
     }
@@ -77,14 +88,14 @@ impl RedHatBoyContext {
         self
     }
 
-    fn set_on(mut self, position: i16) -> Self {
+    fn set_on(mut self, position: i32) -> Self {
         let position = position - PLAYER_HEIGHT;
         self.position.y = position;
         self
     }
 
     fn play_jump_sound(self) -> Self {
-        if let Err(err) = self.audio.play_sound(&self.jump_sound, LOOPING::NO) {
+        if let Err(err) = self.audio.play_sound(&self.jump_sound, Looping::No) {
             log!("Error playing jump sound {:#?}", err);
         }
         self
@@ -112,6 +123,11 @@ impl<S> RedHatBoyState<S> {
     pub fn context(&self) -> &RedHatBoyContext {
         &self.context
     }
+
+    pub fn travel(mut self, distance: i32) -> Self {
+        self.context = self.context.travel(distance);
+        self
+    }
 }
 
 impl RedHatBoyState<Idle> {
@@ -121,7 +137,7 @@ impl RedHatBoyState<Idle> {
             _state: Running {},
         }
     }
-    pub fn new(audio: Audio, jump_sound: Sound) -> Self {
+    pub fn new(audio: Audio, jump_sound: Sound, animations: Rc<Animations>) -> Self {
         RedHatBoyState {
             context: RedHatBoyContext {
                 frame: 0,
@@ -132,28 +148,29 @@ impl RedHatBoyState<Idle> {
                 velocity: Point { x: 0, y: 0 },
                 audio,
                 jump_sound,
+                animations,
             },
             _state: Idle {},
         }
     }
 
-    pub fn frame_name(&self) -> &str {
-        IDLE_FRAME_NAME
+    pub fn clip(&self) -> &str {
+        IDLE_CLIP
     }
 
     pub fn update(mut self) -> Self {
-        self.context = self.context.update(IDLE_FRAMES);
+        self.context = self.context.update(IDLE_CLIP);
         self
     }
 }
 
 impl RedHatBoyState<Running> {
-    pub fn frame_name(&self) -> &str {
-        RUN_FRAME_NAME
+    pub fn clip(&self) -> &str {
+        RUN_CLIP
     }
 
     pub fn update(mut self) -> Self {
-        self.context = self.context.update(RUNNING_FRAMES);
+        self.context = self.context.update(RUN_CLIP);
         self
     }
 
@@ -178,22 +195,23 @@ impl RedHatBoyState<Running> {
         }
     }
 
-    pub fn land_on(self, position: i16) -> RedHatBoyState<Running> {
+    pub fn land_on(self, position: i32) -> RedHatBoyState<Running> {
         RedHatBoyState {
-            context: self.context.set_on(position as i16),
+            context: self.context.set_on(position),
             _state: Running,
         }
     }
 }
 
 impl RedHatBoyState<Sliding> {
-    pub fn frame_name(&self) -> &str {
-        SLIDING_FRAME_NAME
+    pub fn clip(&self) -> &str {
+        SLIDING_CLIP
     }
 
     pub fn update(mut self) -> SlidingEndState {
-        self.context = self.context.update(SLIDING_FRAMES);
-        if self.context.frame >= SLIDING_FRAMES {
+        let (context, event) = self.context.advance(SLIDING_CLIP);
+        self.context = context;
+        if event.is_some() {
             SlidingEndState::Complete(self.stand())
         } else {
             SlidingEndState::Sliding(self)
@@ -214,7 +232,7 @@ impl RedHatBoyState<Sliding> {
         }
     }
 
-    pub fn land_on(self, position: i16) -> RedHatBoyState<Sliding> {
+    pub fn land_on(self, position: i32) -> RedHatBoyState<Sliding> {
         RedHatBoyState {
             context: self.context.set_on(position),
             _state: Sliding,
@@ -223,20 +241,20 @@ impl RedHatBoyState<Sliding> {
 }
 
 impl RedHatBoyState<Jumping> {
-    pub fn frame_name(&self) -> &str {
-        JUMPING_FRAME_NAME
+    pub fn clip(&self) -> &str {
+        JUMPING_CLIP
     }
 
     pub fn update(mut self) -> JumpingEndState {
-        self.context = self.context.update(JUMPING_FRAMES);
+        self.context = self.context.update(JUMPING_CLIP);
         if self.context.position.y >= FLOOR {
-            JumpingEndState::Complete(self.land_on(HEIGHT.into()))
+            JumpingEndState::Complete(self.land_on(HEIGHT))
         } else {
             JumpingEndState::Jumping(self)
         }
     }
 
-    pub fn land_on(self, position: i16) -> RedHatBoyState<Running> {
+    pub fn land_on(self, position: i32) -> RedHatBoyState<Running> {
         RedHatBoyState {
             context: self.context.reset_frame().set_on(position),
             _state: Running,
@@ -252,13 +270,14 @@ impl RedHatBoyState<Jumping> {
 }
 
 impl RedHatBoyState<Falling> {
-    pub fn frame_name(&self) -> &str {
-        FALLING_FRAME_NAME
+    pub fn clip(&self) -> &str {
+        FALLING_CLIP
     }
 
     pub fn update(mut self) -> FallingEndState {
-        self.context = self.context.update(FALLING_FRAMES);
-        if self.context.frame >= FALLING_FRAMES {
+        let (context, event) = self.context.advance(FALLING_CLIP);
+        self.context = context;
+        if event.is_some() {
             FallingEndState::Complete(self.fall())
         } else {
             FallingEndState::Falling(self)
@@ -274,8 +293,8 @@ impl RedHatBoyState<Falling> {
 }
 
 impl RedHatBoyState<KnockedOut> {
-    pub fn frame_name(&self) -> &str {
-        FALLING_FRAME_NAME
+    pub fn clip(&self) -> &str {
+        FALLING_CLIP
     }
 }
 
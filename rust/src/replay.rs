@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    benchmark, browser,
+    engine::KeyState,
+    game,
+    input::{self, KeyBindings},
+    store::LocalStore,
+};
+
+const RECORD_PARAMETER: &str = "record";
+const REPLAY_PARAMETER: &str = "replay";
+const MAX_RECORDED_KEYS: usize = 64;
+
+// Ticks are stored as runs of identical key sets, each key set as a bitmask
+// into `keys`, so holding a key for a minute costs one entry rather than 3600.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Recording {
+    pub seed: u32,
+    // Left out while it holds the defaults, which is also what recordings made
+    // before bindings were configurable played with.
+    #[serde(default, skip_serializing_if = "KeyBindings::is_default")]
+    pub bindings: KeyBindings,
+    keys: Vec<String>,
+    runs: Vec<(u32, u64)>,
+}
+
+impl Recording {
+    pub fn new(seed: u32) -> Self {
+        Recording {
+            seed,
+            bindings: KeyBindings::default(),
+            keys: vec![],
+            runs: vec![],
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|err| anyhow!("Could not parse recording {:#?}", err))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self)
+            .map_err(|err| anyhow!("Could not serialize recording {:#?}", err))
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.runs.iter().map(|(ticks, _mask)| ticks).sum()
+    }
+
+    fn push(&mut self, keystate: &KeyState) {
+        let mask = keystate
+            .pressed_keys()
+            .fold(0, |mask, code| match self.key_index(code) {
+                Some(index) => mask | 1 << index,
+                None => mask,
+            });
+        match self.runs.last_mut() {
+            Some((ticks, last_mask)) if *last_mask == mask => *ticks += 1,
+            _ => self.runs.push((1, mask)),
+        }
+    }
+
+    fn key_index(&mut self, code: &str) -> Option<usize> {
+        match self.keys.iter().position(|key| key == code) {
+            Some(index) => Some(index),
+            None if self.keys.len() < MAX_RECORDED_KEYS => {
+                self.keys.push(code.to_string());
+                Some(self.keys.len() - 1)
+            }
+            None => None,
+        }
+    }
+
+    fn keystate(&self, mask: u64) -> KeyState {
+        let mut keystate = KeyState::new();
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(index, _code)| mask & (1 << index) != 0)
+            .for_each(|(_index, code)| keystate.set_pressed(code));
+        keystate
+    }
+}
+
+thread_local! {
+    static ACTIVE_RECORDER: RefCell<Option<InputRecorder>> = const { RefCell::new(None) };
+}
+
+pub fn active_recording() -> Option<Recording> {
+    ACTIVE_RECORDER.with(|active| active.borrow().as_ref().map(InputRecorder::recording))
+}
+
+#[derive(Clone)]
+pub struct InputRecorder {
+    recording: Rc<RefCell<Recording>>,
+}
+
+impl InputRecorder {
+    pub fn new(seed: u32) -> Self {
+        InputRecorder::with_bindings(seed, KeyBindings::default())
+    }
+
+    pub fn with_bindings(seed: u32, bindings: KeyBindings) -> Self {
+        InputRecorder {
+            recording: Rc::new(RefCell::new(Recording {
+                bindings,
+                ..Recording::new(seed)
+            })),
+        }
+    }
+
+    pub fn record(&self, keystate: &KeyState) {
+        self.recording.borrow_mut().push(keystate);
+    }
+
+    pub fn recording(&self) -> Recording {
+        self.recording.borrow().clone()
+    }
+}
+
+pub struct Replay {
+    recording: Recording,
+    run: usize,
+    tick: u32,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Replay {
+            recording,
+            run: 0,
+            tick: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.recording.seed
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.recording.ticks()
+    }
+
+    pub fn bindings(&self) -> &KeyBindings {
+        &self.recording.bindings
+    }
+}
+
+impl Iterator for Replay {
+    type Item = KeyState;
+
+    fn next(&mut self) -> Option<KeyState> {
+        let (ticks, mask) = *self.recording.runs.get(self.run)?;
+        self.tick += 1;
+        if self.tick >= ticks {
+            self.run += 1;
+            self.tick = 0;
+        }
+        Some(self.recording.keystate(mask))
+    }
+}
+
+pub enum InputMode {
+    Live,
+    Record(InputRecorder),
+    Replay(Replay),
+}
+
+impl InputMode {
+    // `?record` captures every tick, `?replay=<url>` plays a saved recording back
+    // and `?benchmark` alone plays the scripted session. The walk seed and key
+    // bindings are pinned in each case so the course and controls match the inputs,
+    // which is why `?seed` is only read here and a replay ignores it.
+    pub async fn from_query() -> Result<InputMode> {
+        if let Some(path) = browser::query_parameter(REPLAY_PARAMETER) {
+            let json = browser::fetch_text(&path).await?;
+            Ok(InputMode::replay(Recording::from_json(&json)?))
+        } else if benchmark::requested() {
+            let seed = game::query_seed().unwrap_or(benchmark::BENCHMARK_SEED);
+            Ok(InputMode::replay(benchmark::scripted_session(seed)))
+        } else if browser::query_parameter(RECORD_PARAMETER).is_some() {
+            let seed = game::query_seed().unwrap_or_else(rand::random);
+            game::set_seed(seed);
+            let recorder = InputRecorder::with_bindings(seed, KeyBindings::load(&LocalStore));
+            ACTIVE_RECORDER.with(|active| active.replace(Some(recorder.clone())));
+            Ok(InputMode::Record(recorder))
+        } else {
+            if let Some(seed) = game::query_seed() {
+                game::set_seed(seed);
+            }
+            Ok(InputMode::Live)
+        }
+    }
+
+    fn replay(recording: Recording) -> InputMode {
+        let replay = Replay::new(recording);
+        game::set_seed(replay.seed());
+        input::set_bindings(replay.bindings().clone());
+        InputMode::Replay(replay)
+    }
+
+    pub fn next_keystate<'a>(&mut self, live: &'a KeyState) -> Cow<'a, KeyState> {
+        match self {
+            InputMode::Live => Cow::Borrowed(live),
+            InputMode::Record(recorder) => {
+                recorder.record(live);
+                Cow::Borrowed(live)
+            }
+            InputMode::Replay(replay) => match replay.next() {
+                Some(keystate) => Cow::Owned(keystate),
+                None => Cow::Borrowed(live),
+            },
+        }
+    }
+}
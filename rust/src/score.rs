@@ -0,0 +1,53 @@
+use anyhow::Result;
+
+use crate::engine::{Point, Renderer, TextAlign, TextStyle};
+
+const PIXELS_PER_METRE: u32 = 50;
+pub const OBSTACLE_BONUS: u32 = 25;
+
+const HUD_STYLE: TextStyle = TextStyle {
+    font: "24px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Left,
+};
+const HUD_POSITION: Point = Point { x: 16, y: 36 };
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Score {
+    travelled: u32,
+    cleared: u32,
+}
+
+impl Score {
+    pub fn new() -> Self {
+        Score::default()
+    }
+
+    pub fn add_distance(&mut self, pixels: i32) {
+        self.travelled += pixels.max(0) as u32;
+    }
+
+    pub fn obstacle_cleared(&mut self) {
+        self.cleared += 1;
+    }
+
+    pub fn distance(&self) -> u32 {
+        self.travelled / PIXELS_PER_METRE
+    }
+
+    pub fn cleared(&self) -> u32 {
+        self.cleared
+    }
+
+    pub fn total(&self) -> u32 {
+        self.distance() + self.cleared * OBSTACLE_BONUS
+    }
+
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        renderer.draw_text(
+            &format!("{} m  Score {}", self.distance(), self.total()),
+            &HUD_POSITION,
+            &HUD_STYLE,
+        )
+    }
+}
@@ -1,64 +1,248 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 
-use web_sys::HtmlImageElement;
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{engine::{Image, Point, SpriteSheet}, obstacles::{Platform, FLOATING_PLATFORM_SPRITES, FLOATING_PLATFORM_BOUNDING_BOXES, Obstacle, Barrier, LOW_PLATFORM, FIRST_PLATFORM, HIGH_PLATFORM}};
+use crate::{
+    engine::{Image, ImageHandle, Point, Rect, SpriteSheet},
+    obstacles::{Barrier, Obstacle, Platform},
+};
 
-fn create_floating_platform(sprite_sheet: Rc<SpriteSheet>, position: Point) -> Platform {
-    Platform::new(
-        sprite_sheet,
-        position,
-        &FLOATING_PLATFORM_SPRITES,
-        &FLOATING_PLATFORM_BOUNDING_BOXES,
-    )
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
 }
 
-pub fn stone_and_platform(
-    stone: HtmlImageElement,
-    sprite_sheet: Rc<SpriteSheet>,
-    offset_x: i16,
-) -> Vec<Box<dyn Obstacle>> {
-    const INITIAL_STONE_OFFSET: i16 = 150;
-    const STONE_ON_GROUND: i16 = 520;
-    vec![
-        Box::new(Barrier::new(Image::new(
-            stone,
-            Point {
-                x: offset_x + INITIAL_STONE_OFFSET,
-                y: STONE_ON_GROUND,
-            },
-        ))),
-        Box::new(create_floating_platform(
-            sprite_sheet,
-            Point {
-                x: offset_x + FIRST_PLATFORM,
-                y: LOW_PLATFORM,
-            },
-        )),
-    ]
+impl From<&Rect> for BoundingBox {
+    fn from(rect: &Rect) -> Self {
+        BoundingBox {
+            x: rect.x(),
+            y: rect.y(),
+            width: rect.width,
+            height: rect.height,
+        }
+    }
 }
 
-pub fn platform_and_stone(
-    stone: HtmlImageElement,
-    sprite_sheet: Rc<SpriteSheet>,
-    offset_x: i16,
-) -> Vec<Box<dyn Obstacle>> {
-    const INITIAL_STONE_OFFSET: i16 = 150;
-    const STONE_ON_GROUND: i16 = 520;
-    vec![
-        Box::new(Barrier::new(Image::new(
-            stone,
-            Point {
-                x: offset_x + FIRST_PLATFORM,
-                y: STONE_ON_GROUND,
-            },
-        ))),
-        Box::new(create_floating_platform(
-            sprite_sheet,
-            Point {
-                x: offset_x + INITIAL_STONE_OFFSET,
-                y: HIGH_PLATFORM,
-            },
-        )),
-    ]
+impl From<&BoundingBox> for Rect {
+    fn from(bounding_box: &BoundingBox) -> Self {
+        Rect::new_from_x_y(
+            bounding_box.x,
+            bounding_box.y,
+            bounding_box.width,
+            bounding_box.height,
+        )
+    }
+}
+
+/// One obstacle of a segment, positioned relative to where the segment starts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ObstacleDefinition {
+    /// `image` is the key of an image in the asset manifest.
+    Barrier { image: String, x: i32, y: i32 },
+    /// `sprites` are cells of the tile sheet, drawn left to right.
+    Platform {
+        x: i32,
+        y: i32,
+        sprites: Vec<String>,
+        bounding_boxes: Vec<BoundingBox>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SegmentDefinition {
+    pub name: String,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    /// Stays out of the pool until the difficulty curve gets this far.
+    #[serde(default)]
+    pub difficulty: u32,
+    pub obstacles: Vec<ObstacleDefinition>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SegmentFile {
+    pub segments: Vec<SegmentDefinition>,
+}
+
+impl SegmentFile {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|err| anyhow!("Could not parse segments {:#?}", err))
+    }
+}
+
+/// The segments a walk is built from, checked against the loaded images and
+/// tile sheet so a bad file fails at load time rather than mid-run. The first
+/// segment always starts the walk; after that each is picked by weight.
+pub struct SegmentPool {
+    segments: Vec<SegmentDefinition>,
+    images: HashMap<String, ImageHandle>,
+    sheet: Rc<SpriteSheet>,
+}
+
+impl SegmentPool {
+    pub fn new(
+        file: SegmentFile,
+        image: impl Fn(&str) -> Result<ImageHandle>,
+        sheet: Rc<SpriteSheet>,
+    ) -> Result<Self> {
+        let mut problems = vec![];
+        let mut images = HashMap::new();
+        if file.segments.is_empty() {
+            problems.push("there are no segments".to_string());
+        }
+        file.segments.iter().for_each(|segment| {
+            let mut problem =
+                |message: String| problems.push(format!("segment {}: {}", segment.name, message));
+            if segment.obstacles.is_empty() {
+                problem("has no obstacles".to_string());
+            }
+            segment
+                .obstacles
+                .iter()
+                .for_each(|obstacle| match obstacle {
+                    ObstacleDefinition::Barrier { image: key, .. } => {
+                        if !images.contains_key(key) {
+                            match image(key) {
+                                Ok(handle) => {
+                                    images.insert(key.clone(), handle);
+                                }
+                                Err(err) => problem(format!("{:#}", err)),
+                            }
+                        }
+                    }
+                    ObstacleDefinition::Platform {
+                        sprites,
+                        bounding_boxes,
+                        ..
+                    } => {
+                        if sprites.is_empty() {
+                            problem("has a platform with no sprites".to_string());
+                        }
+                        sprites
+                            .iter()
+                            .filter(|sprite| sheet.cell(sprite).is_none())
+                            .for_each(|sprite| {
+                                problem(format!("sprite {} is not in the tile sheet", sprite))
+                            });
+                        if bounding_boxes.is_empty() {
+                            problem("has a platform with no bounding boxes".to_string());
+                        }
+                        if bounding_boxes
+                            .iter()
+                            .any(|bounding_box| bounding_box.width <= 0 || bounding_box.height <= 0)
+                        {
+                            problem("has an empty bounding box".to_string());
+                        }
+                    }
+                });
+        });
+        let total_weight: u32 = file.segments.iter().map(|segment| segment.weight).sum();
+        if !file.segments.is_empty() && total_weight == 0 {
+            problems.push("every segment has a weight of 0".to_string());
+        }
+        if !problems.is_empty() {
+            return Err(anyhow!("Invalid segments: {}", problems.join("; ")));
+        }
+        Ok(SegmentPool {
+            segments: file.segments,
+            images,
+            sheet,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.segments
+            .get(index)
+            .map(|segment| segment.name.as_str())
+    }
+
+    /// Picks the index of the next segment up to `max_difficulty`, in proportion
+    /// to the weights. With nothing to pick from it falls back to the first.
+    pub fn choose(&self, rng: &mut impl Rng, max_difficulty: u32) -> usize {
+        let weight = |segment: &SegmentDefinition| {
+            if segment.difficulty <= max_difficulty {
+                segment.weight
+            } else {
+                0
+            }
+        };
+        let total_weight: u32 = self.segments.iter().map(weight).sum();
+        if total_weight == 0 {
+            return 0;
+        }
+        let mut roll = rng.gen_range(0..total_weight);
+        self.segments
+            .iter()
+            .position(|segment| {
+                if roll < weight(segment) {
+                    true
+                } else {
+                    roll -= weight(segment);
+                    false
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn create(&self, index: usize, offset_x: i32) -> Vec<Box<dyn Obstacle>> {
+        self.segments
+            .get(index)
+            .map(|segment| {
+                segment
+                    .obstacles
+                    .iter()
+                    .map(|obstacle| self.create_obstacle(obstacle, offset_x))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn create_obstacle(&self, obstacle: &ObstacleDefinition, offset_x: i32) -> Box<dyn Obstacle> {
+        match obstacle {
+            ObstacleDefinition::Barrier { image, x, y } => Box::new(Barrier::new(Image::new(
+                self.images[image].clone(),
+                Point {
+                    x: offset_x + x,
+                    y: *y,
+                },
+            ))),
+            ObstacleDefinition::Platform {
+                x,
+                y,
+                sprites,
+                bounding_boxes,
+            } => {
+                let sprites: Vec<&str> = sprites.iter().map(String::as_str).collect();
+                let bounding_boxes: Vec<Rect> = bounding_boxes.iter().map(Rect::from).collect();
+                Box::new(Platform::new(
+                    self.sheet.clone(),
+                    Point {
+                        x: offset_x + x,
+                        y: *y,
+                    },
+                    &sprites,
+                    &bounding_boxes,
+                ))
+            }
+        }
+    }
 }
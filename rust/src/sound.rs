@@ -2,7 +2,9 @@ use anyhow::{anyhow, Result};
 use js_sys::ArrayBuffer;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, AudioDestinationNode, AudioNode};
+use web_sys::{
+    AudioBuffer, AudioBufferSourceNode, AudioContext, AudioNode, AudioScheduledSourceNode, GainNode,
+};
 
 pub fn create_audio_context() -> Result<AudioContext> {
     AudioContext::new().map_err(|err| anyhow!("Could not create audio context: {:#?}", err))
@@ -13,35 +15,94 @@ fn create_buffer_source(ctx: &AudioContext) -> Result<AudioBufferSourceNode> {
         .map_err(|err| anyhow!("Error creating buffer source {:#?}", err))
 }
 
-fn connect_with_audio_node(
-    buffer_source: &AudioBufferSourceNode,
-    destination: &AudioDestinationNode,
-) -> Result<AudioNode> {
-    buffer_source
-        .connect_with_audio_node(&destination)
+fn connect_with_audio_node(source: &AudioNode, destination: &AudioNode) -> Result<AudioNode> {
+    source
+        .connect_with_audio_node(destination)
         .map_err(|err| anyhow!("Error connecting audio source to destionation {:#?}", err))
 }
 
-fn create_track_source(ctx: &AudioContext, buffer: &AudioBuffer) -> Result<AudioBufferSourceNode> {
+/// A gain node already feeding `destination`, e.g. a mixer bus or the speakers.
+pub fn create_gain(ctx: &AudioContext, destination: &AudioNode) -> Result<GainNode> {
+    let gain = ctx
+        .create_gain()
+        .map_err(|err| anyhow!("Error creating gain node {:#?}", err))?;
+    connect_with_audio_node(&gain, destination)?;
+    Ok(gain)
+}
+
+// Holds the gain at its current value from now on, so a fade or a volume
+// change starts from what is actually playing.
+fn hold_gain(ctx: &AudioContext, gain: &GainNode) -> Result<()> {
+    let now = ctx.current_time();
+    let value = gain.gain().value();
+    gain.gain()
+        .cancel_scheduled_values(now)
+        .and_then(|param| param.set_value_at_time(value, now))
+        .map(|_param| ())
+        .map_err(|err| anyhow!("Could not hold the gain {:#?}", err))
+}
+
+pub fn set_gain(ctx: &AudioContext, gain: &GainNode, value: f32) -> Result<()> {
+    hold_gain(ctx, gain)?;
+    gain.gain()
+        .set_value_at_time(value, ctx.current_time())
+        .map(|_param| ())
+        .map_err(|err| anyhow!("Could not set the gain {:#?}", err))
+}
+
+fn create_track_source(
+    ctx: &AudioContext,
+    buffer: &AudioBuffer,
+    destination: &AudioNode,
+) -> Result<(AudioBufferSourceNode, GainNode)> {
     let track_source = create_buffer_source(ctx)?;
-    track_source.set_buffer(Some(&buffer));
-    connect_with_audio_node(&track_source, &ctx.destination())?;
-    Ok(track_source)
+    track_source.set_buffer(Some(buffer));
+    let gain = create_gain(ctx, destination)?;
+    connect_with_audio_node(&track_source, &gain)?;
+    Ok((track_source, gain))
 }
 
-pub enum LOOPING {
-    NO,
-    YES,
+pub enum Looping {
+    No,
+    Yes,
 }
 
-pub fn play_sound(ctx: &AudioContext, buffer: &AudioBuffer, looping: LOOPING) -> Result<()> {
-    let track_source = create_track_source(ctx, buffer)?;
-    if matches!(looping, LOOPING::YES) {
+/// Plays `buffer` into `destination` through a gain node of its own, which
+/// is returned with the source so the sound can be faded out on its own.
+pub fn play_sound(
+    ctx: &AudioContext,
+    buffer: &AudioBuffer,
+    looping: Looping,
+    destination: &AudioNode,
+) -> Result<(AudioBufferSourceNode, GainNode)> {
+    let (track_source, gain) = create_track_source(ctx, buffer, destination)?;
+    if matches!(looping, Looping::Yes) {
         track_source.set_loop(true);
     }
     track_source
         .start()
-        .map_err(|err| anyhow!("Could not start sound!{:#?}", err))
+        .map_err(|err| anyhow!("Could not start sound!{:#?}", err))?;
+    Ok((track_source, gain))
+}
+
+pub fn stop_sound(source: &AudioBufferSourceNode, when: f64) -> Result<()> {
+    AsRef::<AudioScheduledSourceNode>::as_ref(source)
+        .stop_with_when(when)
+        .map_err(|err| anyhow!("Could not stop sound {:#?}", err))
+}
+
+pub fn fade_out(
+    ctx: &AudioContext,
+    source: &AudioBufferSourceNode,
+    gain: &GainNode,
+    seconds: f64,
+) -> Result<()> {
+    hold_gain(ctx, gain)?;
+    let end = ctx.current_time() + seconds;
+    gain.gain()
+        .linear_ramp_to_value_at_time(0.0, end)
+        .map_err(|err| anyhow!("Could not fade out sound {:#?}", err))?;
+    stop_sound(source, end)
 }
 
 pub async fn decode_audio_data(
@@ -49,7 +110,7 @@ pub async fn decode_audio_data(
     array_buffer: &ArrayBuffer,
 ) -> Result<AudioBuffer> {
     JsFuture::from(
-        ctx.decode_audio_data(&array_buffer)
+        ctx.decode_audio_data(array_buffer)
             .map_err(|err| anyhow!("Could not decode audio from array buffer {:#?}", err))?,
     )
     .await
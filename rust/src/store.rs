@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::browser::storage;
+
+// Values are kept as JSON strings so the in-memory backend behaves exactly
+// like localStorage, including failing on data that no longer parses.
+pub trait Store {
+    fn get_item(&self, key: &str) -> Result<Option<String>>;
+    fn set_item(&self, key: &str, value: &str) -> Result<()>;
+    fn remove(&self, key: &str) -> Result<()>;
+
+    fn get_json(&self, key: &str) -> Result<Option<Value>> {
+        self.get_item(key)?
+            .map(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|err| anyhow!("Could not parse {} {:#?}", key, err))
+            })
+            .transpose()
+    }
+
+    fn set_json(&self, key: &str, value: &Value) -> Result<()> {
+        self.set_item(key, &value.to_string())
+    }
+}
+
+impl dyn Store + '_ {
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.get_json(key)?
+            .map(|value| {
+                serde_json::from_value(value)
+                    .map_err(|err| anyhow!("Could not parse {} {:#?}", key, err))
+            })
+            .transpose()
+    }
+
+    pub fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value)
+            .map_err(|err| anyhow!("Could not serialize {} {:#?}", key, err))?;
+        self.set_json(key, &value)
+    }
+}
+
+pub struct LocalStore;
+
+impl Store for LocalStore {
+    fn get_item(&self, key: &str) -> Result<Option<String>> {
+        storage::get_item(key)
+    }
+
+    fn set_item(&self, key: &str, value: &str) -> Result<()> {
+        storage::set_item(key, value)
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        storage::remove(key)
+    }
+
+    fn get_json(&self, key: &str) -> Result<Option<Value>> {
+        storage::get(key)
+    }
+
+    fn set_json(&self, key: &str, value: &Value) -> Result<()> {
+        storage::set(key, value)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    items: Rc<RefCell<HashMap<String, String>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+}
+
+impl Store for MemoryStore {
+    fn get_item(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.items.borrow().get(key).cloned())
+    }
+
+    fn set_item(&self, key: &str, value: &str) -> Result<()> {
+        self.items
+            .borrow_mut()
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        self.items.borrow_mut().remove(key);
+        Ok(())
+    }
+}
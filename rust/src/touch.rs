@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::engine::{KeyState, Point, Rect, Renderer, TextAlign, TextStyle};
+
+/// Touch gestures and on-screen buttons reach the action layer as key codes
+/// with this prefix, like the gamepad's.
+pub const TOUCH_PREFIX: &str = "Touch";
+pub const TAP: &str = "TouchTap";
+pub const SWIPE_DOWN: &str = "TouchSwipeDown";
+/// Gestures are pressed for a single tick, like `FOCUS_LOST`.
+pub const GESTURE_CODES: [&str; 2] = [TAP, SWIPE_DOWN];
+
+const TAP_DISTANCE: i32 = 16;
+const TAP_TIME: f64 = 300.0;
+const SWIPE_DISTANCE: i32 = 48;
+const SWIPE_TIME: f64 = 500.0;
+
+const BUTTON_COLOR: &str = "rgba(255, 255, 255, 0.3)";
+const BUTTON_STYLE: TextStyle = TextStyle {
+    font: "20px 'Ken Future'",
+    color: "white",
+    align: TextAlign::Center,
+};
+
+pub struct TouchButton {
+    pub code: &'static str,
+    pub label: &'static str,
+    pub area: Rect,
+}
+
+pub fn default_buttons() -> Vec<TouchButton> {
+    vec![
+        TouchButton {
+            code: "TouchSlide",
+            label: "Slide",
+            area: Rect::new_from_x_y(16, 474, 112, 80),
+        },
+        TouchButton {
+            code: "TouchJump",
+            label: "Jump",
+            area: Rect::new_from_x_y(472, 474, 112, 80),
+        },
+        TouchButton {
+            code: "TouchPause",
+            label: "II",
+            area: Rect::new_from_x_y(532, 12, 56, 44),
+        },
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointerKind {
+    Mouse,
+    Pen,
+    Touch,
+}
+
+/// A pointer event in canvas pixels, timed in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pointer {
+    Down {
+        id: i32,
+        kind: PointerKind,
+        position: Point,
+        time: f64,
+    },
+    Move {
+        id: i32,
+        position: Point,
+        time: f64,
+    },
+    Up {
+        id: i32,
+        position: Point,
+        time: f64,
+    },
+    Cancel {
+        id: i32,
+    },
+}
+
+struct Contact {
+    start: Point,
+    start_time: f64,
+    button: Option<&'static str>,
+    applied: bool,
+    swiped: bool,
+}
+
+/// Turns raw pointer sequences into gesture and button codes. Buttons only
+/// appear, and only take presses, once a finger has touched the screen.
+pub struct GestureRecognizer {
+    buttons: Vec<TouchButton>,
+    buttons_visible: bool,
+    contacts: HashMap<i32, Contact>,
+    pressed: Vec<&'static str>,
+    // Everything pressed since the last update, kept down until one has seen it.
+    latched: Vec<&'static str>,
+}
+
+impl GestureRecognizer {
+    pub fn new(buttons: Vec<TouchButton>) -> Self {
+        GestureRecognizer {
+            buttons,
+            buttons_visible: false,
+            contacts: HashMap::new(),
+            pressed: vec![],
+            latched: vec![],
+        }
+    }
+
+    pub fn buttons_visible(&self) -> bool {
+        self.buttons_visible
+    }
+
+    pub fn handle(&mut self, pointer: Pointer) {
+        match pointer {
+            Pointer::Down {
+                id,
+                kind,
+                position,
+                time,
+            } => {
+                if kind == PointerKind::Touch {
+                    self.buttons_visible = true;
+                }
+                let button = self.button_at(&position);
+                self.contacts.insert(
+                    id,
+                    Contact {
+                        start: position,
+                        start_time: time,
+                        button,
+                        applied: false,
+                        swiped: false,
+                    },
+                );
+            }
+            Pointer::Move { id, position, time } => {
+                if let Some(contact) = self.contacts.get_mut(&id) {
+                    if contact.button.is_none()
+                        && !contact.swiped
+                        && is_swipe(contact, position, time)
+                    {
+                        contact.swiped = true;
+                        self.pressed.push(SWIPE_DOWN);
+                    }
+                }
+            }
+            Pointer::Up { id, position, time } => {
+                if let Some(contact) = self.contacts.remove(&id) {
+                    match contact.button {
+                        // A press that started and ended within one frame still counts once.
+                        Some(button) if !contact.applied => self.pressed.push(button),
+                        Some(_button) => {}
+                        None if contact.swiped => {}
+                        None if is_swipe(&contact, position, time) => self.pressed.push(SWIPE_DOWN),
+                        None if is_tap(&contact, position, time) => self.pressed.push(TAP),
+                        None => {}
+                    }
+                }
+            }
+            Pointer::Cancel { id } => {
+                self.contacts.remove(&id);
+            }
+        }
+    }
+
+    /// Holds the buttons currently under a finger and presses any gestures
+    /// recognised since the last update. Called once per frame.
+    pub fn apply(&mut self, keystate: &mut KeyState) {
+        self.buttons
+            .iter()
+            .for_each(|button| keystate.set_released(button.code));
+        self.latched
+            .extend(self.contacts.values_mut().filter_map(|contact| {
+                contact.applied = true;
+                contact.button
+            }));
+        self.latched.append(&mut self.pressed);
+        self.latched.sort_unstable();
+        self.latched.dedup();
+        self.latched
+            .iter()
+            .for_each(|code| keystate.set_pressed(code));
+    }
+
+    /// Lets go of the buttons that are no longer touched, once an update has seen them.
+    pub fn updated(&mut self) {
+        self.latched.clear();
+    }
+
+    pub fn draw(&self, renderer: &dyn Renderer) -> Result<()> {
+        if !self.buttons_visible {
+            return Ok(());
+        }
+        self.buttons.iter().try_for_each(|button| {
+            renderer.fill_rect(&button.area, BUTTON_COLOR);
+            renderer.draw_text(
+                button.label,
+                &Point {
+                    x: button.area.x() + button.area.width / 2,
+                    y: button.area.y() + button.area.height / 2 + 8,
+                },
+                &BUTTON_STYLE,
+            )
+        })
+    }
+
+    fn button_at(&self, position: &Point) -> Option<&'static str> {
+        if !self.buttons_visible {
+            return None;
+        }
+        self.buttons
+            .iter()
+            .find(|button| button.area.contains(position))
+            .map(|button| button.code)
+    }
+}
+
+fn is_tap(contact: &Contact, position: Point, time: f64) -> bool {
+    (position.x - contact.start.x).abs() <= TAP_DISTANCE
+        && (position.y - contact.start.y).abs() <= TAP_DISTANCE
+        && time - contact.start_time <= TAP_TIME
+}
+
+fn is_swipe(contact: &Contact, position: Point, time: f64) -> bool {
+    let dx = position.x - contact.start.x;
+    let dy = position.y - contact.start.y;
+    dy >= SWIPE_DISTANCE && dy > dx.abs() && time - contact.start_time <= SWIPE_TIME
+}
@@ -0,0 +1,120 @@
+use rust_webpack_template::animation::{
+    AnimationClip, AnimationEvent, Animations, ClipSettings, PlayMode,
+};
+use rust_webpack_template::engine::{Audio, ImageHandle, Sheet, Sound};
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+
+const CELL: &str =
+    r#"{"frame":{"x":0,"y":0,"w":10,"h":10},"spriteSourceSize":{"x":0,"y":0,"w":10,"h":10}}"#;
+
+fn sheet(frames: &[&str], meta: &str) -> Sheet {
+    let frames: Vec<String> = frames
+        .iter()
+        .map(|name| format!("\"{}\":{}", name, CELL))
+        .collect();
+    serde_json::from_str(&format!(
+        "{{\"frames\":{{{}}},\"meta\":{}}}",
+        frames.join(","),
+        meta
+    ))
+    .unwrap()
+}
+
+fn clip(frames: usize, mode: PlayMode) -> AnimationClip {
+    AnimationClip::new(
+        (1..=frames).map(|frame| frame.to_string()).collect(),
+        ClipSettings {
+            frame_ticks: 1,
+            mode,
+        },
+    )
+}
+
+fn played(clip: &AnimationClip, ticks: usize) -> Vec<String> {
+    let mut tick = 0;
+    (0..ticks)
+        .map(|_| {
+            let frame = clip.frame_at(tick).unwrap().to_string();
+            tick = clip.advance(tick).0;
+            frame
+        })
+        .collect()
+}
+
+#[test]
+fn frames_are_grouped_into_clips_in_number_order() {
+    let sheet: Sheet = serde_json::from_str(include_str!("../static/rhb.json")).unwrap();
+    let animations = Animations::from_sheet(&sheet);
+
+    let idle = animations.clip("Idle").unwrap();
+
+    assert_eq!(animations.len(), 6);
+    assert_eq!(idle.frames().len(), 10);
+    assert_eq!(idle.frames()[1], "Idle (2).png");
+    assert_eq!(idle.frames()[9], "Idle (10).png");
+    assert_eq!(idle.duration(), 30);
+    assert_eq!(animations.clip("Slide").unwrap().mode(), PlayMode::Once);
+}
+
+#[test]
+fn other_naming_schemes_group_the_same_way() {
+    let animations = Animations::from_sheet(&sheet(
+        &["walk_01.png", "walk_02.png", "walk-3.png", "logo.png"],
+        "{}",
+    ));
+
+    assert_eq!(
+        animations.clip("walk").unwrap().frames(),
+        ["walk_01.png", "walk_02.png", "walk-3.png"]
+    );
+    assert_eq!(animations.clip("logo").unwrap().frames(), ["logo.png"]);
+}
+
+#[test]
+fn a_looping_clip_starts_over() {
+    let clip = clip(3, PlayMode::Loop);
+
+    assert_eq!(played(&clip, 5), ["1", "2", "3", "1", "2"]);
+    assert_eq!(clip.advance(2), (0, Some(AnimationEvent::Looped)));
+}
+
+#[test]
+fn a_ping_pong_clip_plays_back_and_forth() {
+    let clip = clip(3, PlayMode::PingPong);
+
+    assert_eq!(played(&clip, 7), ["1", "2", "3", "2", "1", "2", "3"]);
+}
+
+#[test]
+fn a_clip_played_once_completes_and_holds_its_last_frame() {
+    let clip = clip(3, PlayMode::Once);
+
+    assert_eq!(clip.advance(1), (2, Some(AnimationEvent::Completed)));
+    assert_eq!(clip.advance(2), (2, Some(AnimationEvent::Completed)));
+    assert_eq!(played(&clip, 5), ["1", "2", "3", "3", "3"]);
+}
+
+#[test]
+fn a_different_sheet_changes_how_long_the_boy_slides() {
+    let mut boy = RedHatBoy::new(
+        sheet(
+            &["Run (1).png", "Slide (1).png", "Slide (2).png"],
+            r#"{"animations":{"Slide":{"mode":"once","frame_ticks":2}}}"#,
+        ),
+        ImageHandle::headless("rhb.png", 10, 10),
+        Audio::headless(),
+        Sound::headless("SFX_Jump_23.mp3"),
+    );
+    boy.run_right();
+    boy.slide();
+
+    let ticks = (1..100)
+        .find(|_| {
+            boy.update();
+            boy.state_name() != "Sliding"
+        })
+        .unwrap();
+
+    assert_eq!(ticks, 3);
+    assert!(boy.missing_frames().is_empty());
+}
@@ -0,0 +1,179 @@
+use rust_webpack_template::animation::{AnimationClip, Animations, PlayMode};
+use rust_webpack_template::engine::{Audio, ImageHandle, Sheet, Sound};
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+
+fn frame(name: &str, x: i32, duration: u16) -> String {
+    format!(
+        r#""{}": {{
+            "frame": {{"x":{},"y":0,"w":10,"h":10}},
+            "rotated": false,
+            "trimmed": false,
+            "spriteSourceSize": {{"x":0,"y":0,"w":10,"h":10}},
+            "sourceSize": {{"w":10,"h":10}},
+            "duration": {}
+        }}"#,
+        name, x, duration
+    )
+}
+
+// Frame names that sort differently from the file's order, to show the tags
+// follow the file.
+fn export(tags: &str, slices: &str) -> String {
+    format!(
+        r#"{{
+            "frames": {{ {}, {}, {}, {} }},
+            "meta": {{
+                "app": "https://www.aseprite.org/",
+                "version": "1.3",
+                "image": "boy.png",
+                "format": "RGBA8888",
+                "size": {{"w":40,"h":10}},
+                "scale": "1",
+                "frameTags": [{}],
+                "layers": [],
+                "slices": [{}]
+            }}
+        }}"#,
+        frame("boy d.aseprite", 0, 100),
+        frame("boy c.aseprite", 10, 50),
+        frame("boy b.aseprite", 20, 50),
+        frame("boy a.aseprite", 30, 200),
+        tags,
+        slices
+    )
+}
+
+fn played(clip: &AnimationClip) -> Vec<&str> {
+    (0..clip.duration())
+        .map(|tick| clip.frame_at(tick).unwrap())
+        .collect()
+}
+
+#[test]
+fn tags_become_clips_timed_by_each_frames_duration() {
+    let sheet = Sheet::from_json(&export(
+        r#"{"name": "Run", "from": 0, "to": 2, "direction": "forward"}"#,
+        "",
+    ))
+    .unwrap();
+    let animations = Animations::from_sheet(&sheet);
+    let run = animations.clip("Run").unwrap();
+
+    assert_eq!(sheet.frames.len(), 4);
+    assert_eq!(sheet.meta.image.as_deref(), Some("boy.png"));
+    assert_eq!(run.mode(), PlayMode::Loop);
+    assert_eq!(
+        played(run),
+        [
+            vec!["boy d.aseprite"; 6],
+            vec!["boy c.aseprite"; 3],
+            vec!["boy b.aseprite"; 3]
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn tag_directions_set_the_order_and_mode() {
+    let sheet = Sheet::from_json(&export(
+        r#"{"name": "Back", "from": 1, "to": 3, "direction": "reverse"},
+           {"name": "Bounce", "from": 0, "to": 2, "direction": "pingpong_reverse"},
+           {"name": "Fall", "from": 2, "to": 3, "direction": "forward", "repeat": "1"}"#,
+        "",
+    ))
+    .unwrap();
+    let animations = Animations::from_sheet(&sheet);
+
+    assert_eq!(
+        animations.clip("Back").unwrap().frames(),
+        ["boy a.aseprite", "boy b.aseprite", "boy c.aseprite"]
+    );
+    assert_eq!(
+        animations.clip("Bounce").unwrap().mode(),
+        PlayMode::PingPong
+    );
+    assert_eq!(
+        animations.clip("Bounce").unwrap().frames(),
+        ["boy b.aseprite", "boy c.aseprite", "boy d.aseprite"]
+    );
+    assert_eq!(animations.clip("Fall").unwrap().mode(), PlayMode::Once);
+}
+
+#[test]
+fn array_exports_keep_their_order() {
+    let json = export(
+        r#"{"name": "Run", "from": 0, "to": 1, "direction": "forward"}"#,
+        "",
+    );
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let frames: Vec<serde_json::Value> = ["boy d.aseprite", "boy c.aseprite"]
+        .iter()
+        .map(|name| {
+            let mut frame = value["frames"][name].clone();
+            frame["filename"] = (*name).into();
+            frame
+        })
+        .collect();
+    value["frames"] = frames.into();
+
+    let sheet = Sheet::from_json(&value.to_string()).unwrap();
+
+    assert_eq!(
+        Animations::from_sheet(&sheet).clip("Run").unwrap().frames(),
+        ["boy d.aseprite", "boy c.aseprite"]
+    );
+}
+
+#[test]
+fn a_tag_past_the_last_frame_is_an_error() {
+    let err = Sheet::from_json(&export(
+        r#"{"name": "Run", "from": 2, "to": 9, "direction": "forward"}"#,
+        "",
+    ))
+    .err()
+    .unwrap();
+
+    assert!(err.to_string().contains("Tag Run"));
+}
+
+#[test]
+fn slice_keys_hold_until_the_next_key() {
+    let sheet = Sheet::from_json(&export(
+        "",
+        r##"{"name": "hitbox", "color": "#0000ffff", "keys": [
+            {"frame": 1, "bounds": {"x":1,"y":2,"w":3,"h":4}},
+            {"frame": 3, "bounds": {"x":5,"y":6,"w":7,"h":8}}
+        ]}"##,
+    ))
+    .unwrap();
+    let slice = |name: &str| {
+        sheet.frames[name]
+            .slices
+            .get("hitbox")
+            .map(|bounds| (bounds.x, bounds.w))
+    };
+
+    assert_eq!(slice("boy d.aseprite"), None);
+    assert_eq!(slice("boy c.aseprite"), Some((1, 3)));
+    assert_eq!(slice("boy b.aseprite"), Some((1, 3)));
+    assert_eq!(slice("boy a.aseprite"), Some((5, 7)));
+}
+
+#[test]
+fn the_boy_collides_with_his_hitbox_slice() {
+    let sheet = Sheet::from_json(&export(
+        r#"{"name": "Idle", "from": 0, "to": 3, "direction": "forward"}"#,
+        r#"{"name": "hitbox", "keys": [{"frame": 0, "bounds": {"x":2,"y":3,"w":4,"h":5}}]}"#,
+    ))
+    .unwrap();
+    let boy = RedHatBoy::new(
+        sheet,
+        ImageHandle::headless("boy.png", 40, 10),
+        Audio::headless(),
+        Sound::headless("SFX_Jump_23.mp3"),
+    );
+    let hitbox = boy.bounding_box();
+
+    assert_eq!((hitbox.width, hitbox.height), (4, 5));
+    assert_eq!(hitbox.y(), boy.pos_y() + 3);
+}
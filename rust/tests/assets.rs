@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::executor::block_on;
+use rust_webpack_template::assets::{
+    AssetErrors, AssetLoader, AssetManager, AssetManifest, LoadProgress,
+};
+use rust_webpack_template::engine::{ImageHandle, Sound};
+
+const MANIFEST: &str = include_str!("../static/assets.json");
+
+// Serves the files in `static` as headless assets, recording what it was asked for.
+#[derive(Default)]
+struct StaticLoader {
+    missing: Vec<&'static str>,
+    requests: RefCell<Vec<String>>,
+}
+
+impl StaticLoader {
+    fn without(missing: &[&'static str]) -> Self {
+        StaticLoader {
+            missing: missing.to_vec(),
+            ..StaticLoader::default()
+        }
+    }
+
+    fn request(&self, path: &str) -> Result<()> {
+        self.requests.borrow_mut().push(path.to_string());
+        if self.missing.contains(&path) {
+            Err(anyhow!("404 Not Found"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl AssetLoader for StaticLoader {
+    async fn load_image(&self, path: &str) -> Result<ImageHandle> {
+        self.request(path)?;
+        Ok(ImageHandle::headless(path, 100, 100))
+    }
+
+    async fn load_text(&self, path: &str) -> Result<String> {
+        self.request(path)?;
+        match path {
+            "rhb.json" => Ok(include_str!("../static/rhb.json").to_string()),
+            "tiles.json" => Ok(include_str!("../static/tiles.json").to_string()),
+            "segments.json" => Ok(include_str!("../static/segments.json").to_string()),
+            "difficulty.json" => Ok(include_str!("../static/difficulty.json").to_string()),
+            _ => Ok("not a sheet".to_string()),
+        }
+    }
+
+    async fn load_sound(&self, path: &str) -> Result<Sound> {
+        self.request(path)?;
+        Ok(Sound::headless(path))
+    }
+}
+
+fn manifest() -> AssetManifest {
+    AssetManifest::from_json(MANIFEST).unwrap()
+}
+
+#[test]
+fn the_manifest_lists_everything_the_game_loads() {
+    let mut assets = AssetManager::new();
+    let progress = LoadProgress::new();
+
+    block_on(assets.load(&manifest(), &StaticLoader::default(), &progress)).unwrap();
+
+    assert_eq!(progress.loaded(), 9);
+    assert_eq!(progress.fraction(), 1.0);
+    assert_eq!(assets.image("stone").unwrap().name(), "Stone.png");
+    assert!(assets
+        .sprite_sheet("tiles")
+        .unwrap()
+        .cell("13.png")
+        .is_some());
+    assert!(assets.sheet("rhb").is_ok());
+    assert!(assets.sound("music").is_ok());
+    assert!(assets
+        .text("segments")
+        .unwrap()
+        .contains("stone_and_platform"));
+}
+
+#[test]
+fn assets_are_looked_up_by_key_and_kind() {
+    let mut assets = AssetManager::new();
+    block_on(assets.load(&manifest(), &StaticLoader::default(), &LoadProgress::new())).unwrap();
+
+    assert!(assets.sound("stone").is_err());
+    assert!(assets.image("rhb").is_err());
+    assert!(assets.image("dog").is_err());
+}
+
+#[test]
+fn every_failed_asset_is_reported() {
+    let mut assets = AssetManager::new();
+    let progress = LoadProgress::new();
+
+    let err = block_on(assets.load(
+        &manifest(),
+        &StaticLoader::without(&["Stone.png", "tiles.json"]),
+        &progress,
+    ))
+    .unwrap_err();
+    let errors = err.downcast_ref::<AssetErrors>().unwrap();
+
+    let mut failed: Vec<(&str, &str)> = errors
+        .0
+        .iter()
+        .map(|error| (error.key.as_str(), error.source.as_str()))
+        .collect();
+    failed.sort();
+    assert_eq!(
+        failed,
+        [("stone", "Stone.png"), ("tiles", "tiles.json + tiles.png")]
+    );
+    assert!(err.to_string().contains("stone (Stone.png): 404 Not Found"));
+    assert_eq!(progress.loaded(), 7);
+    assert_eq!(progress.failed(), 2);
+    assert!(assets.contains("background"));
+}
+
+#[test]
+fn retrying_only_loads_what_failed() {
+    let mut assets = AssetManager::new();
+    let _ = block_on(assets.load(
+        &manifest(),
+        &StaticLoader::without(&["Stone.png"]),
+        &LoadProgress::new(),
+    ));
+    let loader = StaticLoader::default();
+
+    block_on(assets.load(&manifest(), &loader, &LoadProgress::new())).unwrap();
+
+    assert_eq!(*loader.requests.borrow(), ["Stone.png"]);
+}
+
+#[test]
+fn a_malformed_sheet_is_an_asset_error() {
+    let manifest = AssetManifest::from_json(
+        r#"{"assets": [{"key": "dog", "kind": "sheet", "json": "dog.json", "image": "dog.png"}]}"#,
+    )
+    .unwrap();
+
+    let err = block_on(AssetManager::new().load(
+        &manifest,
+        &StaticLoader::default(),
+        &LoadProgress::new(),
+    ))
+    .unwrap_err();
+
+    assert!(err.to_string().contains("dog (dog.json + dog.png)"));
+}
+
+#[test]
+fn unknown_asset_kinds_are_rejected() {
+    assert!(AssetManifest::from_json(
+        r#"{"assets": [{"key": "x", "kind": "video", "path": "x.webm"}]}"#
+    )
+    .is_err());
+}
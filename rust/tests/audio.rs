@@ -0,0 +1,204 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{
+    Audio, AudioState, DrawCommand, Game, HeadlessRenderer, KeyState, Sound,
+};
+use rust_webpack_template::game::{Walk, WalkTheDog, SOUND_HINT};
+use rust_webpack_template::mixer::{Bus, MixerSettings};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+use rust_webpack_template::sound::Looping;
+use rust_webpack_template::store::{MemoryStore, Store};
+
+fn walk(audio: &Audio) -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        audio.clone(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    )
+}
+
+#[test]
+fn volumes_are_clamped_to_whole_steps() {
+    let mut mixer = MixerSettings::default();
+
+    mixer.set_volume(Bus::Music, 1.4);
+    mixer.set_volume(Bus::Sfx, 0.33);
+
+    assert_eq!(mixer.volume(Bus::Music), 1.0);
+    assert!((mixer.volume(Bus::Sfx) - 0.3).abs() < 1e-6);
+}
+
+#[test]
+fn muting_silences_only_the_master_bus() {
+    let mixer = MixerSettings {
+        muted: true,
+        ..MixerSettings::default()
+    };
+
+    assert_eq!(mixer.gain(Bus::Master), 0.0);
+    assert_eq!(mixer.gain(Bus::Music), mixer.music);
+    assert_eq!(mixer.volume(Bus::Master), 1.0);
+}
+
+#[test]
+fn mixer_settings_are_saved_and_loaded() {
+    let store: Rc<dyn Store> = Rc::new(MemoryStore::new());
+    let mut mixer = MixerSettings::default();
+    mixer.set_volume(Bus::Music, 0.2);
+    mixer.muted = true;
+
+    mixer.save(&*store);
+
+    assert_eq!(MixerSettings::load(&*store), mixer);
+}
+
+#[test]
+fn unreadable_mixer_settings_fall_back_to_defaults() {
+    let store: Rc<dyn Store> = Rc::new(MemoryStore::new());
+    store.set_item("walk_the_dog.mixer", "not json").unwrap();
+
+    assert_eq!(MixerSettings::load(&*store), MixerSettings::default());
+}
+
+#[test]
+fn clones_of_the_audio_share_one_mixer() {
+    let audio = Audio::headless();
+    let clone = audio.clone();
+
+    clone.set_mixer(MixerSettings {
+        muted: true,
+        ..MixerSettings::default()
+    });
+
+    assert!(audio.mixer().muted);
+}
+
+#[test]
+fn a_stopped_sound_reports_it() {
+    let audio = Audio::headless();
+    let handle = audio
+        .play_sound(&Sound::headless("SFX_Jump_23.mp3"), Looping::No)
+        .unwrap();
+
+    assert!(!handle.is_stopped());
+    handle.fade_out(0.5).unwrap();
+    assert!(handle.is_stopped());
+}
+
+#[test]
+fn music_fades_when_the_boy_is_knocked_out_and_restarts_with_the_next_walk() {
+    let audio = Audio::headless();
+    let mut walk = walk(&audio);
+    walk.play_music(Sound::headless("background_song.mp3"));
+    let playback = walk.music_playback().unwrap().clone();
+
+    walk.boy_mut().run_right();
+    walk.boy_mut().knock_out();
+    for _ in 0..100 {
+        if walk.knocked_out() {
+            break;
+        }
+        walk.update();
+    }
+
+    assert!(playback.is_stopped());
+    assert!(walk.music_playback().is_none());
+
+    let walk = Walk::reset(walk);
+
+    assert!(walk.music_playback().is_some());
+    assert_eq!(
+        audio.played(),
+        vec!["background_song.mp3", "background_song.mp3"]
+    );
+}
+
+#[test]
+fn the_mute_key_toggles_and_saves_the_mixer() {
+    let audio = Audio::headless();
+    let store: Rc<dyn Store> = Rc::new(MemoryStore::new());
+    let mut game = WalkTheDog::with_store(walk(&audio), store.clone());
+    let mut mute = KeyState::new();
+    mute.set_pressed("KeyM");
+
+    game.update(&mute).unwrap();
+    game.update(&mute).unwrap();
+
+    assert!(audio.mixer().muted);
+    assert!(MixerSettings::load(&*store).muted);
+
+    game.update(&KeyState::new()).unwrap();
+    game.update(&mute).unwrap();
+
+    assert!(!audio.mixer().muted);
+}
+
+#[test]
+fn music_waits_for_suspended_audio_to_resume() {
+    let audio = Audio::headless_suspended();
+    let music = audio
+        .play_music(&Sound::headless("background_song.mp3"))
+        .unwrap();
+    let jump = audio
+        .play_sound(&Sound::headless("SFX_Jump_23.mp3"), Looping::No)
+        .unwrap();
+
+    assert!(!music.is_started());
+    assert!(audio.played().is_empty());
+
+    audio.resume();
+
+    assert_eq!(audio.state(), AudioState::Running);
+    assert!(music.is_started());
+    assert!(!jump.is_started());
+    assert_eq!(audio.played(), vec!["background_song.mp3"]);
+}
+
+#[test]
+fn a_sound_stopped_before_the_audio_resumes_never_starts() {
+    let audio = Audio::headless_suspended();
+    let music = audio
+        .play_music(&Sound::headless("background_song.mp3"))
+        .unwrap();
+
+    music.stop().unwrap();
+    audio.resume();
+
+    assert!(!music.is_started());
+    assert!(audio.played().is_empty());
+}
+
+#[test]
+fn the_game_asks_for_a_gesture_while_the_audio_is_suspended() {
+    let audio = Audio::headless_suspended();
+    let game = WalkTheDog::from(walk(&audio));
+    let shows_hint = |game: &WalkTheDog| {
+        let renderer = HeadlessRenderer::new();
+        game.draw(&renderer).unwrap();
+        renderer.commands().iter().any(
+            |command| matches!(command, DrawCommand::DrawText { text, .. } if text == SOUND_HINT),
+        )
+    };
+
+    assert!(shows_hint(&game));
+    audio.resume();
+    assert!(!shows_hint(&game));
+}
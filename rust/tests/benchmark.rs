@@ -0,0 +1,78 @@
+use rust_webpack_template::benchmark::{scripted_session, Benchmark, Summary};
+use rust_webpack_template::replay::Replay;
+
+#[test]
+fn summary_uses_nearest_rank_percentiles() {
+    let samples: Vec<f64> = (1..=100).rev().map(f64::from).collect();
+
+    assert_eq!(
+        Summary::from_samples(&samples),
+        Summary {
+            mean: 50.5,
+            p50: 50.0,
+            p95: 95.0,
+            p99: 99.0,
+            max: 100.0,
+        }
+    );
+}
+
+#[test]
+fn summary_of_no_samples_is_zero() {
+    assert_eq!(Summary::from_samples(&[]), Summary::default());
+}
+
+#[test]
+fn report_counts_frames_without_memory_growth() {
+    let mut benchmark = Benchmark::new(1, 3, 1024.0);
+    benchmark.record_frame(1.0, 2.0, 1024.0);
+    assert!(!benchmark.is_complete());
+    benchmark.record_frame(2.0, 2.0, 2048.0);
+    benchmark.record_frame(3.0, 2.0, 2048.0);
+    assert!(benchmark.is_complete());
+
+    let report = benchmark.report();
+
+    assert_eq!(report.implementation, "rust");
+    assert_eq!(report.frames, 3);
+    assert_eq!(report.update_ms.max, 3.0);
+    assert_eq!(report.frame_ms.mean, 4.0);
+    assert_eq!(report.gc_free_frames, 2);
+    assert_eq!(report.memory_growth_bytes, 1024.0);
+}
+
+#[test]
+fn report_serializes_the_shared_schema() {
+    let json = Benchmark::new(1, 0, 0.0).report().to_json();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    [
+        "implementation",
+        "seed",
+        "frames",
+        "update_ms",
+        "draw_ms",
+        "frame_ms",
+        "gc_free_frames",
+        "initial_memory_bytes",
+        "final_memory_bytes",
+        "memory_growth_bytes",
+    ]
+    .iter()
+    .for_each(|key| assert!(value.get(key).is_some(), "missing {}", key));
+}
+
+#[test]
+fn scripted_session_is_a_minute_of_running_and_jumping() {
+    let replay = Replay::new(scripted_session(5));
+
+    assert_eq!(replay.seed(), 5);
+    assert_eq!(replay.ticks(), 3600);
+    assert!(Replay::new(scripted_session(5)).all(|keystate| keystate.is_pressed("ArrowRight")));
+    assert_eq!(
+        Replay::new(scripted_session(5))
+            .filter(|keystate| keystate.is_pressed("Space"))
+            .count(),
+        40 * 6
+    );
+}
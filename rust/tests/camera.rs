@@ -0,0 +1,131 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{Audio, Camera, DrawCommand, HeadlessRenderer, Point};
+use rust_webpack_template::game::Walk;
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+
+// The shipped course, which opens with a stone to run into.
+fn walk() -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    )
+}
+
+fn stone_positions(walk: &Walk) -> Vec<Point> {
+    let renderer = HeadlessRenderer::new();
+    walk.draw(&renderer).unwrap();
+    renderer
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            DrawCommand::DrawEntireImage { image, position } if image == "Stone.png" => {
+                Some(position)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn points_convert_between_world_and_screen() {
+    let camera = Camera::at(Point { x: 1000, y: -50 });
+    let world = Point { x: 1250, y: 100 };
+
+    let screen = camera.to_screen(&world);
+
+    assert_eq!(screen, Point { x: 250, y: 150 });
+    assert_eq!(camera.to_world(&screen), world);
+}
+
+#[test]
+fn a_followed_target_stays_at_its_place_on_screen() {
+    let start = Point { x: -20, y: 449 };
+    let mut camera = Camera::following(start);
+
+    camera.follow(&Point { x: 70_000, y: 449 });
+
+    assert_eq!(camera.to_screen(&Point { x: 70_000, y: 449 }), start);
+}
+
+#[test]
+fn the_camera_rises_after_a_high_target_and_settles_back_on_the_ground() {
+    let mut camera = Camera::following(Point { x: 0, y: 449 });
+    camera.follow_vertically(40);
+
+    (0..10).for_each(|_| camera.follow(&Point { x: 0, y: 449 }));
+    assert_eq!(camera.position().y, 0);
+
+    (0..60).for_each(|_| camera.follow(&Point { x: 0, y: -200 }));
+    assert_eq!(camera.position().y, -240);
+
+    (0..60).for_each(|_| camera.follow(&Point { x: 0, y: 449 }));
+    assert_eq!(camera.position().y, 0);
+}
+
+#[test]
+fn a_shake_moves_the_view_but_not_the_camera_and_dies_away() {
+    let mut camera = Camera::at(Point { x: 100, y: 0 });
+
+    camera.shake(6, 4);
+
+    assert!(camera.is_shaking());
+    assert_ne!(camera.view_position(), camera.position());
+    (0..4).for_each(|_| camera.update());
+    assert!(!camera.is_shaking());
+    assert_eq!(camera.view_position(), Point { x: 100, y: 0 });
+}
+
+#[test]
+fn obstacles_stay_put_in_the_world_as_the_camera_passes() {
+    let mut walk = walk();
+    let before = stone_positions(&walk);
+    walk.boy_mut().run_right();
+
+    (0..10).for_each(|_| walk.update());
+
+    let travelled = walk.boy().pos_x() - -20;
+    assert_eq!(walk.camera().x(), travelled);
+    assert_eq!(
+        stone_positions(&walk)[0],
+        Point {
+            x: before[0].x - travelled,
+            y: before[0].y,
+        }
+    );
+}
+
+#[test]
+fn running_into_an_obstacle_shakes_the_camera() {
+    let mut walk = walk();
+    walk.boy_mut().run_right();
+
+    for _ in 0..1000 {
+        walk.update();
+        if walk.boy().falling() {
+            break;
+        }
+    }
+
+    assert!(walk.boy().falling());
+    assert!(walk.camera().is_shaking());
+}
@@ -0,0 +1,46 @@
+#![allow(dead_code)]
+
+// The game's shipped assets, loaded the way the browser would hand them over.
+// Each test file builds the boy, walk or game it needs from these itself.
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{ImageHandle, Sheet, Sound, SpriteSheet};
+use rust_webpack_template::segments::SegmentFile;
+
+pub fn rhb_sheet() -> Sheet {
+    serde_json::from_str(include_str!("../../static/rhb.json")).unwrap()
+}
+
+pub fn rhb_image() -> ImageHandle {
+    ImageHandle::headless("rhb.png", 1989, 366)
+}
+
+pub fn jump_sound() -> Sound {
+    Sound::headless("SFX_Jump_23.mp3")
+}
+
+pub fn background() -> ImageHandle {
+    ImageHandle::headless("BG.png", 1000, 750)
+}
+
+pub fn stone() -> ImageHandle {
+    ImageHandle::headless("Stone.png", 90, 54)
+}
+
+pub fn tiles() -> Rc<SpriteSheet> {
+    let sheet: Sheet = serde_json::from_str(include_str!("../../static/tiles.json")).unwrap();
+    Rc::new(SpriteSheet::new(
+        sheet,
+        ImageHandle::headless("tiles.png", 650, 485),
+    ))
+}
+
+pub fn segment_file() -> SegmentFile {
+    SegmentFile::from_json(include_str!("../../static/segments.json")).unwrap()
+}
+
+pub fn difficulty_curve() -> DifficultyCurve {
+    DifficultyCurve::from_json(include_str!("../../static/difficulty.json")).unwrap()
+}
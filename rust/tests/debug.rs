@@ -0,0 +1,126 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::debug::{DebugOverlay, DEBUG_KEY, OBSTACLE_COLOR, PLAYER_COLOR};
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{Audio, DrawCommand, Game, HeadlessRenderer, KeyState};
+use rust_webpack_template::game::{Walk, WalkTheDog};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+
+// The shipped course on the given curve; the velocity shown depends on it.
+fn walk(curve: DifficultyCurve) -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(curve),
+        0,
+    )
+}
+
+fn strokes(renderer: &HeadlessRenderer, color: &str) -> usize {
+    renderer
+        .commands()
+        .iter()
+        .filter(|command| {
+            matches!(command, DrawCommand::StrokeRect { color: stroke, .. } if stroke == color)
+        })
+        .count()
+}
+
+fn texts(renderer: &HeadlessRenderer) -> Vec<String> {
+    renderer
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            DrawCommand::DrawText { text, .. } => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn the_overlay_toggles_once_per_press() {
+    let mut overlay = DebugOverlay::new(false);
+    let mut held = KeyState::new();
+    held.set_pressed(DEBUG_KEY);
+
+    overlay.handle_keys(&held);
+    overlay.handle_keys(&held);
+    assert!(overlay.is_visible());
+    overlay.handle_keys(&KeyState::new());
+    overlay.handle_keys(&held);
+
+    assert!(!overlay.is_visible());
+}
+
+#[test]
+fn timings_are_smoothed() {
+    let mut overlay = DebugOverlay::new(true);
+
+    overlay.record_frame(10.0, 20.0);
+
+    assert!((overlay.update_ms() - 1.0).abs() < 1e-9);
+    assert!((overlay.draw_ms() - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn every_hitbox_is_outlined() {
+    let walk = walk(DifficultyCurve::default());
+    let renderer = HeadlessRenderer::new();
+
+    walk.draw_debug(&renderer).unwrap();
+
+    assert_eq!(strokes(&renderer, PLAYER_COLOR), 1);
+    // The first segment's stone and the three parts of its platform.
+    assert_eq!(strokes(&renderer, OBSTACLE_COLOR), 4);
+}
+
+#[test]
+fn the_game_shows_the_boy_state_and_walk_counters() {
+    let mut game = WalkTheDog::from(walk(DifficultyCurve::default()));
+    let renderer = HeadlessRenderer::new();
+    let mut keystate = KeyState::new();
+    keystate.set_pressed("ArrowRight");
+    (0..3).for_each(|_| game.update(&keystate).unwrap());
+
+    game.draw_debug(&renderer).unwrap();
+
+    let texts = texts(&renderer);
+    assert!(texts.contains(&"state Running".to_string()), "{:?}", texts);
+    assert!(texts.iter().any(|text| text.starts_with("velocity 4.0, ")));
+    assert!(texts.iter().any(|text| text.starts_with("timeline ")));
+    assert!(texts.iter().any(|text| text.starts_with("obstacles ")));
+}
+
+#[test]
+fn the_velocity_includes_the_difficulty_speed_up() {
+    let curve = DifficultyCurve::from_json(
+        r#"{ "stages": [{ "distance": 0, "speed": 1.5, "obstacle_buffer": 20, "segment_difficulty": 0 }] }"#,
+    )
+    .unwrap();
+    let mut walk = walk(curve);
+    walk.boy_mut().run_right();
+    walk.update();
+    let renderer = HeadlessRenderer::new();
+
+    walk.draw_debug(&renderer).unwrap();
+
+    assert!(texts(&renderer)
+        .iter()
+        .any(|text| text.starts_with("velocity 6.0, ")));
+}
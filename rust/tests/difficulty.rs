@@ -0,0 +1,108 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::{Difficulty, DifficultyCurve};
+use rust_webpack_template::engine::Audio;
+use rust_webpack_template::game::Walk;
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+
+const CURVE: &str = r#"{ "stages": [
+    { "distance": 0, "speed": 1.0, "obstacle_buffer": 20, "segment_difficulty": 0 },
+    { "distance": 100, "speed": 2.0, "obstacle_buffer": 10, "segment_difficulty": 1 }
+] }"#;
+
+#[test]
+fn the_curve_eases_between_stages_and_holds_after_the_last() {
+    let curve = DifficultyCurve::from_json(CURVE).unwrap();
+
+    assert_eq!(
+        curve.at(50),
+        Difficulty {
+            speed: 1.5,
+            obstacle_buffer: 15,
+            segment_difficulty: 0,
+        }
+    );
+    assert_eq!(curve.at(100).segment_difficulty, 1);
+    assert_eq!(curve.at(5000).speed, 2.0);
+}
+
+#[test]
+fn the_default_curve_never_changes() {
+    let curve = DifficultyCurve::default();
+
+    assert_eq!(curve.at(0), curve.at(10_000));
+    assert_eq!(curve.at(0).speed, 1.0);
+}
+
+#[test]
+fn malformed_curves_are_rejected() {
+    [
+        r#"{ "stages": [] }"#,
+        r#"{ "stages": [{ "distance": 10, "speed": 1.0, "obstacle_buffer": 20, "segment_difficulty": 0 }] }"#,
+        r#"{ "stages": [
+            { "distance": 0, "speed": 1.0, "obstacle_buffer": 20, "segment_difficulty": 0 },
+            { "distance": 0, "speed": 2.0, "obstacle_buffer": 20, "segment_difficulty": 0 }
+        ] }"#,
+        r#"{ "stages": [{ "distance": 0, "speed": 0.0, "obstacle_buffer": 20, "segment_difficulty": 0 }] }"#,
+        r#"{ "stages": [{ "distance": 0, "speed": 1.0, "obstacle_buffer": -5, "segment_difficulty": 0 }] }"#,
+    ]
+    .iter()
+    .for_each(|json| assert!(DifficultyCurve::from_json(json).is_err(), "{}", json));
+}
+
+#[test]
+fn a_zero_gap_is_allowed() {
+    let json = r#"{ "stages": [{ "distance": 0, "speed": 1.0, "obstacle_buffer": 0, "segment_difficulty": 0 }] }"#;
+
+    assert_eq!(
+        DifficultyCurve::from_json(json)
+            .unwrap()
+            .at(0)
+            .obstacle_buffer,
+        0
+    );
+}
+
+// The shipped course on the shipped curve, so the speed-up is the real one.
+fn walk() -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(common::difficulty_curve()),
+        0,
+    )
+}
+
+#[test]
+fn the_walk_speeds_up_as_it_goes() {
+    let mut walk = walk();
+    walk.boy_mut().run_right();
+    walk.boy_mut().jump();
+    let start = walk.difficulty();
+
+    while walk.score().distance() < 150 && !walk.knocked_out() {
+        walk.update();
+        if walk.boy().pos_y() >= 400 {
+            walk.boy_mut().jump();
+        }
+    }
+
+    assert!(walk.difficulty().speed > start.speed);
+    assert!(walk.difficulty().segment_difficulty > start.segment_difficulty);
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::editor::{Editor, Tool};
+use rust_webpack_template::engine::{
+    Audio, DrawCommand, Game, HeadlessRenderer, KeyState, Point, Rect,
+};
+use rust_webpack_template::game::Walk;
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::score::Score;
+use rust_webpack_template::segments::{ObstacleDefinition, SegmentDefinition, SegmentPool};
+use rust_webpack_template::touch::{Pointer, PointerKind};
+
+// The walk the editor plays its segment back in.
+fn walk() -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    )
+}
+
+fn editor() -> Editor {
+    Editor::new(walk(), common::stone(), common::tiles())
+}
+
+fn press(x: i32, y: i32) -> Pointer {
+    Pointer::Down {
+        id: 1,
+        kind: PointerKind::Mouse,
+        position: Point { x, y },
+        time: 0.0,
+    }
+}
+
+#[test]
+fn clicks_place_snapped_pieces_where_the_grid_has_scrolled_to() {
+    let mut editor = editor();
+    let mut keystate = KeyState::new();
+    keystate.set_pressed("ArrowRight");
+    (0..10).for_each(|_| editor.update(&keystate).unwrap());
+
+    editor.handle_pointer(press(52, 523));
+
+    assert_eq!(
+        editor.pieces(),
+        [ObstacleDefinition::Barrier {
+            image: "stone".to_string(),
+            x: 150,
+            y: 520,
+        }]
+    );
+}
+
+#[test]
+fn clicking_a_piece_removes_it() {
+    let mut editor = editor();
+    editor.select(Tool::Platform);
+    editor.handle_pointer(press(200, 420));
+
+    editor.handle_pointer(press(300, 450));
+
+    assert!(editor.pieces().is_empty());
+}
+
+#[test]
+fn collision_boxes_are_previewed() {
+    let mut editor = editor();
+    let renderer = HeadlessRenderer::new();
+    editor.select(Tool::Platform);
+    editor.handle_pointer(press(200, 420));
+
+    editor.draw(&renderer).unwrap();
+
+    assert!(renderer.commands().contains(&DrawCommand::FillRect {
+        rect: Rect::new_from_x_y(260, 420, 264, 93),
+        color: "rgba(255, 0, 0, 0.4)".to_string(),
+    }));
+}
+
+#[test]
+fn the_export_reads_back_as_a_segment() {
+    let mut editor = editor();
+    editor.handle_pointer(press(150, 520));
+    editor.select(Tool::Platform);
+    editor.handle_pointer(press(200, 420));
+
+    let segment: SegmentDefinition = serde_json::from_str(&editor.export().unwrap()).unwrap();
+
+    assert_eq!(segment.name, "custom");
+    assert_eq!(segment.obstacles, editor.pieces());
+    assert!(matches!(
+        &segment.obstacles[1],
+        ObstacleDefinition::Platform { sprites, bounding_boxes, .. }
+            if sprites.len() == 3 && bounding_boxes.len() == 3
+    ));
+}
+
+#[test]
+fn play_tests_the_segment_on_a_walk_until_paused() {
+    let mut editor = editor();
+    editor.handle_pointer(press(400, 520));
+    editor.play().unwrap();
+    let mut pause = KeyState::new();
+    pause.set_pressed("Escape");
+
+    (0..5).for_each(|_| editor.update(&KeyState::new()).unwrap());
+    assert!(editor.is_playing());
+    assert_ne!(*editor.walk().unwrap().score(), Score::new());
+    editor.update(&pause).unwrap();
+
+    assert!(!editor.is_playing());
+    assert_eq!(editor.pieces().len(), 1);
+}
+
+#[test]
+fn an_empty_segment_cannot_be_played() {
+    let mut editor = editor();
+
+    assert!(editor.play().is_err());
+    assert!(!editor.is_playing());
+}
+
+#[test]
+fn knock_outs_go_back_to_editing() {
+    let mut editor = editor();
+    editor.handle_pointer(press(150, 470));
+    editor.play().unwrap();
+    let keystate = KeyState::new();
+
+    (0..600).for_each(|_| editor.update(&keystate).unwrap());
+
+    assert!(!editor.is_playing());
+}
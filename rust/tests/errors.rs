@@ -0,0 +1,55 @@
+use anyhow::anyhow;
+use rust_webpack_template::engine::{
+    Audio, Game, HeadlessRenderer, ImageHandle, KeyState, Sheet, Sound,
+};
+use rust_webpack_template::error_scene::{error_ui, ErrorScene};
+use rust_webpack_template::game::WalkTheDog;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+
+fn boy_without_frames() -> RedHatBoy {
+    RedHatBoy::new(
+        Sheet::default(),
+        ImageHandle::headless("rhb.png", 1989, 366),
+        Audio::headless(),
+        Sound::headless("SFX_Jump_23.mp3"),
+    )
+}
+
+#[test]
+fn a_missing_clip_is_skipped_and_reported_once() {
+    let boy = boy_without_frames();
+    let renderer = HeadlessRenderer::new();
+
+    boy.draw(&renderer).unwrap();
+    boy.draw(&renderer).unwrap();
+
+    assert!(renderer.commands().is_empty());
+    assert_eq!(boy.missing_frames(), vec!["Idle".to_string()]);
+}
+
+#[test]
+fn updating_an_uninitialized_game_is_an_error() {
+    let mut game = WalkTheDog::new();
+
+    assert!(game.update(&KeyState::new()).is_err());
+    assert!(game.draw(&HeadlessRenderer::new()).is_ok());
+}
+
+#[test]
+fn the_error_ui_escapes_the_message() {
+    let html = error_ui(&anyhow!("<script>"));
+
+    assert!(html.contains("&lt;script&gt;"));
+    assert!(html.contains("id='resume'"));
+}
+
+#[test]
+fn without_its_ui_the_error_scene_resumes_on_the_next_key() {
+    let mut scene = ErrorScene::show(&anyhow!("boom"));
+    let mut held = KeyState::new();
+    held.set_pressed("ArrowRight");
+
+    assert!(!scene.resumed(&held));
+    assert!(!scene.resumed(&KeyState::new()));
+    assert!(scene.resumed(&held));
+}
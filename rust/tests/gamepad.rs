@@ -0,0 +1,122 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{Audio, Game, HeadlessRenderer, KeyState};
+use rust_webpack_template::game::{Walk, WalkTheDog};
+use rust_webpack_template::gamepad::{self, GamepadSnapshot, DEAD_ZONE};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+
+// The game on the shipped course; only the pad's input matters here.
+fn game() -> WalkTheDog {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    WalkTheDog::from(Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    ))
+}
+
+fn pad(buttons: &[usize], axes: [f64; 2]) -> GamepadSnapshot {
+    let mut snapshot = GamepadSnapshot {
+        buttons: vec![false; 17],
+        axes: axes.to_vec(),
+    };
+    buttons
+        .iter()
+        .for_each(|button| snapshot.buttons[*button] = true);
+    snapshot
+}
+
+fn pressed(keystate: &KeyState) -> Vec<&str> {
+    let mut keys: Vec<&str> = keystate.pressed_keys().collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn buttons_follow_the_standard_mapping() {
+    assert_eq!(
+        pad(&[0, 9, 15], [0.0, 0.0]).pressed_codes(),
+        ["GamepadA", "GamepadRight", "GamepadStart"]
+    );
+}
+
+#[test]
+fn a_resting_stick_inside_the_dead_zone_does_nothing() {
+    let drift = DEAD_ZONE * 0.6;
+
+    assert!(pad(&[], [drift, drift]).pressed_codes().is_empty());
+}
+
+#[test]
+fn a_leaning_stick_reads_as_the_d_pad() {
+    assert_eq!(pad(&[], [0.9, 0.1]).pressed_codes(), ["GamepadRight"]);
+    assert_eq!(
+        pad(&[], [-0.7, 0.7]).pressed_codes(),
+        ["GamepadDown", "GamepadLeft"]
+    );
+    assert_eq!(pad(&[15], [1.0, 0.0]).pressed_codes(), ["GamepadRight"]);
+}
+
+#[test]
+fn merging_replaces_the_last_poll_but_keeps_the_keyboard() {
+    let mut keystate = KeyState::new();
+    keystate.set_pressed("Space");
+
+    gamepad::merge(
+        &mut keystate,
+        &[pad(&[0], [0.0, 0.0]), pad(&[1], [0.0, 0.0])],
+    );
+    assert_eq!(pressed(&keystate), ["GamepadA", "GamepadB", "Space"]);
+
+    gamepad::merge(&mut keystate, &[pad(&[], [0.0, 0.0])]);
+    assert_eq!(pressed(&keystate), ["Space"]);
+
+    gamepad::merge(&mut keystate, &[]);
+    assert_eq!(pressed(&keystate), ["Space"]);
+}
+
+#[test]
+fn a_gamepad_plays_the_same_walk_as_the_keyboard() {
+    let play = |keys: &dyn Fn(u32) -> KeyState| {
+        let mut game = game();
+        let renderer = HeadlessRenderer::new();
+        (0..300).for_each(|tick| {
+            game.update(&keys(tick)).unwrap();
+            game.draw(&renderer).unwrap();
+        });
+        renderer.take_commands()
+    };
+    let keyboard = play(&|tick| {
+        let mut keystate = KeyState::new();
+        keystate.set_pressed("ArrowRight");
+        if tick % 60 < 5 {
+            keystate.set_pressed("Space");
+        }
+        keystate
+    });
+    let gamepad = play(&|tick| {
+        let mut keystate = KeyState::new();
+        let buttons: &[usize] = if tick % 60 < 5 { &[0] } else { &[] };
+        gamepad::merge(&mut keystate, &[pad(buttons, [1.0, 0.0])]);
+        keystate
+    });
+
+    assert_eq!(keyboard, gamepad);
+}
@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use rust_webpack_template::high_scores::{escape_html, HighScoreTable, HIGH_SCORE_CAPACITY};
+use rust_webpack_template::store::{MemoryStore, Store};
+
+fn table(store: &MemoryStore) -> HighScoreTable {
+    HighScoreTable::load(Rc::new(store.clone()))
+}
+
+#[test]
+fn an_empty_store_loads_an_empty_table() {
+    let table = table(&MemoryStore::new());
+
+    assert!(table.entries().is_empty());
+    assert_eq!(table.player_name(), "Player");
+    assert!(table.qualifies(0));
+}
+
+#[test]
+fn entries_are_ranked_by_distance() {
+    let store = MemoryStore::new();
+    let mut table = table(&store);
+
+    assert_eq!(table.submit(100, 150, 1, "2024-01-01"), Some(0));
+    assert_eq!(table.submit(300, 300, 2, "2024-01-02"), Some(0));
+    assert_eq!(table.submit(200, 200, 3, "2024-01-03"), Some(1));
+
+    let distances: Vec<u32> = table.entries().iter().map(|entry| entry.distance).collect();
+    assert_eq!(distances, vec![300, 200, 100]);
+}
+
+#[test]
+fn the_table_keeps_only_the_best_runs() {
+    let store = MemoryStore::new();
+    let mut table = table(&store);
+    (1..=HIGH_SCORE_CAPACITY as u32).for_each(|distance| {
+        table.submit(distance * 10, 0, 0, "");
+    });
+
+    assert!(!table.qualifies(10));
+    assert_eq!(table.submit(5, 0, 0, ""), None);
+    assert_eq!(table.submit(15, 0, 0, ""), Some(HIGH_SCORE_CAPACITY - 1));
+    assert_eq!(table.entries().len(), HIGH_SCORE_CAPACITY);
+    assert_eq!(table.entries().last().map(|entry| entry.distance), Some(15));
+}
+
+#[test]
+fn scores_and_names_survive_a_reload() {
+    let store = MemoryStore::new();
+    let mut first = table(&store);
+    let rank = first.submit(120, 170, 42, "2024-05-06").unwrap();
+    first.rename(rank, "  Walker  ");
+
+    let reloaded = table(&store);
+
+    assert_eq!(reloaded.player_name(), "Walker");
+    assert_eq!(reloaded.entries().len(), 1);
+    let entry = &reloaded.entries()[0];
+    assert_eq!(entry.name, "Walker");
+    assert_eq!(entry.score, 170);
+    assert_eq!(entry.seed, 42);
+    assert_eq!(entry.date, "2024-05-06");
+}
+
+#[test]
+fn the_last_name_is_used_for_the_next_run() {
+    let store = MemoryStore::new();
+    let mut table = table(&store);
+    let rank = table.submit(50, 50, 0, "").unwrap();
+    table.rename(rank, "A very long player name");
+
+    table.submit(60, 60, 0, "");
+
+    assert_eq!(table.entries()[0].name, "A very long ");
+}
+
+#[test]
+fn corrupt_data_falls_back_to_an_empty_table() {
+    let store = MemoryStore::new();
+    store
+        .set_item("walk_the_dog.high_scores", "not json")
+        .unwrap();
+
+    let table = table(&store);
+
+    assert!(table.entries().is_empty());
+}
+
+#[test]
+fn names_are_escaped_in_the_table() {
+    let store = MemoryStore::new();
+    let mut table = table(&store);
+    let rank = table.submit(10, 10, 0, "2024-01-01").unwrap();
+    table.rename(rank, "<b>Bob</b>");
+
+    let html = table.to_html(Some(rank));
+
+    assert!(html.contains("<li class='current'>&lt;b&gt;Bob&lt;/b&gt; 10 m"));
+    assert_eq!(escape_html("'&\""), "&#39;&amp;&quot;");
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{
+    Audio, DrawCommand, Game, HeadlessRenderer, KeyState, FOCUS_LOST,
+};
+use rust_webpack_template::game::{Walk, WalkTheDog};
+use rust_webpack_template::input::{Action, KeyBindings};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::replay::{InputRecorder, Recording};
+use rust_webpack_template::segments::SegmentPool;
+use rust_webpack_template::store::{MemoryStore, Store};
+
+// A walk on the shipped course; the bindings are what differ between games.
+fn walk() -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    )
+}
+
+fn keystate(codes: &[&str]) -> KeyState {
+    let mut keystate = KeyState::new();
+    codes.iter().for_each(|code| keystate.set_pressed(code));
+    keystate
+}
+
+#[test]
+fn default_bindings_match_the_original_controls() {
+    let actions = KeyBindings::default().actions(
+        &keystate(&["ArrowRight", "Space", "ArrowDown"]),
+        &KeyState::new(),
+    );
+
+    assert!(actions.is_held(Action::Run));
+    assert!(actions.is_held(Action::Jump));
+    assert!(actions.is_held(Action::Slide));
+    assert!(!actions.is_held(Action::Pause));
+}
+
+#[test]
+fn actions_are_pressed_only_on_the_first_tick() {
+    let bindings = KeyBindings::default();
+    let held = keystate(&["Escape"]);
+
+    let first = bindings.actions(&held, &KeyState::new());
+    let second = bindings.actions(&held, &held);
+
+    assert!(first.was_pressed(Action::Pause));
+    assert!(!second.was_pressed(Action::Pause));
+    assert!(second.is_held(Action::Pause));
+}
+
+#[test]
+fn binding_a_key_takes_it_from_other_actions() {
+    let mut bindings = KeyBindings::default();
+
+    bindings.bind(Action::Jump, "ArrowDown");
+
+    assert_eq!(
+        bindings.keys(Action::Jump),
+        ["GamepadA", "TouchTap", "TouchJump", "ArrowDown"]
+    );
+    assert_eq!(
+        bindings.keys(Action::Slide),
+        ["GamepadDown", "GamepadB", "TouchSwipeDown", "TouchSlide"]
+    );
+}
+
+#[test]
+fn binding_a_gamepad_button_keeps_the_keyboard_key() {
+    let mut bindings = KeyBindings::default();
+
+    bindings.bind(Action::Jump, "GamepadX");
+
+    assert_eq!(
+        bindings.keys(Action::Jump),
+        ["Space", "TouchTap", "TouchJump", "GamepadX"]
+    );
+    assert_eq!(bindings.labels(Action::Pause), ["Escape", "P", "Start"]);
+}
+
+#[test]
+fn the_raw_key_is_reported_for_capturing_but_focus_loss_is_not() {
+    let bindings = KeyBindings::default();
+
+    let actions = bindings.actions(&keystate(&["KeyQ", FOCUS_LOST]), &KeyState::new());
+
+    assert_eq!(actions.pressed_key(), Some("KeyQ"));
+    assert!(actions.focus_lost());
+}
+
+#[test]
+fn bindings_survive_a_reload() {
+    let store = MemoryStore::new();
+    let mut bindings = KeyBindings::load(&store);
+    bindings.bind(Action::Jump, "KeyW");
+    bindings.save(&store);
+
+    assert_eq!(KeyBindings::load(&store), bindings);
+}
+
+#[test]
+fn a_key_taken_from_another_action_stays_taken_after_a_reload() {
+    let store = MemoryStore::new();
+    let mut bindings = KeyBindings::load(&store);
+    bindings.bind(Action::Run, "Space");
+    bindings.save(&store);
+
+    let reloaded = KeyBindings::load(&store);
+    let actions = reloaded.actions(&keystate(&["Space"]), &KeyState::new());
+
+    assert_eq!(reloaded, bindings);
+    assert!(actions.is_held(Action::Run));
+    assert!(!actions.is_held(Action::Jump));
+}
+
+#[test]
+fn saved_tables_missing_an_action_or_device_keep_its_defaults() {
+    let store = MemoryStore::new();
+    store
+        .set_item(
+            "walk_the_dog.key_bindings",
+            r#"{"bindings":{"Jump":["KeyW"]}}"#,
+        )
+        .unwrap();
+
+    let bindings = KeyBindings::load(&store);
+
+    assert_eq!(bindings.labels(Action::Jump), ["W", "A"]);
+    assert_eq!(bindings.labels(Action::Run), ["ArrowRight", "Right"]);
+    assert!(bindings
+        .keys(Action::Jump)
+        .iter()
+        .any(|key| key == "TouchTap"));
+}
+
+#[test]
+fn corrupt_bindings_fall_back_to_the_defaults() {
+    let store = MemoryStore::new();
+    store
+        .set_item("walk_the_dog.key_bindings", "not json")
+        .unwrap();
+
+    assert_eq!(KeyBindings::load(&store), KeyBindings::default());
+}
+
+fn images_after(game: &mut WalkTheDog, keys: &[&str], ticks: usize) -> Vec<DrawCommand> {
+    let renderer = HeadlessRenderer::new();
+    (0..ticks).for_each(|_| game.update(&keystate(keys)).unwrap());
+    game.draw(&renderer).unwrap();
+    renderer.take_commands()
+}
+
+#[test]
+fn the_game_reads_rebound_keys() {
+    let store = MemoryStore::new();
+    let mut bindings = KeyBindings::default();
+    bindings.bind(Action::Run, "KeyD");
+    bindings.save(&store);
+    let mut rebound = WalkTheDog::with_store(walk(), Rc::new(store));
+    let mut old_key = WalkTheDog::with_store(walk(), Rc::new(MemoryStore::new()));
+    let mut standing = WalkTheDog::from(walk());
+
+    let running = images_after(&mut rebound, &["KeyD"], 30);
+    let still = images_after(&mut standing, &[], 30);
+
+    assert_ne!(running, still);
+    assert_eq!(images_after(&mut old_key, &["ArrowRight"], 30), running);
+}
+
+#[test]
+fn jumping_wins_when_jump_and_slide_are_both_held() {
+    let mut both = WalkTheDog::from(walk());
+    let mut jump_only = WalkTheDog::from(walk());
+    images_after(&mut both, &["ArrowRight"], 1);
+    images_after(&mut jump_only, &["ArrowRight"], 1);
+
+    assert_eq!(
+        images_after(&mut both, &["Space", "ArrowDown"], 5),
+        images_after(&mut jump_only, &["Space"], 5)
+    );
+}
+
+#[test]
+fn recordings_carry_their_bindings() {
+    let mut bindings = KeyBindings::default();
+    bindings.bind(Action::Jump, "KeyW");
+    let recorder = InputRecorder::with_bindings(3, bindings.clone());
+    recorder.record(&keystate(&["KeyW"]));
+
+    let json = recorder.recording().to_json().unwrap();
+
+    assert_eq!(Recording::from_json(&json).unwrap().bindings, bindings);
+}
+
+#[test]
+fn recordings_without_bindings_use_the_defaults() {
+    let recording = Recording::from_json(r#"{"seed":5,"keys":[],"runs":[]}"#).unwrap();
+
+    assert_eq!(recording.bindings, KeyBindings::default());
+}
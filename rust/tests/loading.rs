@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::FutureExt;
+use rust_webpack_template::assets::{
+    AssetError, AssetErrors, AssetLoader, AssetManager, AssetManifest, LoadProgress,
+};
+use rust_webpack_template::engine::{DrawCommand, HeadlessRenderer, ImageHandle, Rect, Sound};
+use rust_webpack_template::loading::{draw_loading_scene, load_error_ui};
+
+const MANIFEST: &str = r#"{ "assets": [
+    { "key": "background", "kind": "image", "path": "BG.png" },
+    { "key": "stone", "kind": "image", "path": "Stone.png" },
+    { "key": "music", "kind": "sound", "path": "background_song.mp3" }
+] }"#;
+
+// Sounds never finish loading, so a load can be caught half way through.
+struct StalledLoader;
+
+#[async_trait(?Send)]
+impl AssetLoader for StalledLoader {
+    async fn load_image(&self, path: &str) -> Result<ImageHandle> {
+        Ok(ImageHandle::headless(path, 100, 100))
+    }
+
+    async fn load_text(&self, path: &str) -> Result<String> {
+        Err(anyhow!("Unexpected request for {}", path))
+    }
+
+    async fn load_sound(&self, _path: &str) -> Result<Sound> {
+        futures::future::pending().await
+    }
+}
+
+fn fill_rects(renderer: &HeadlessRenderer) -> Vec<Rect> {
+    renderer
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            DrawCommand::FillRect { rect, .. } => Some(rect),
+            _ => None,
+        })
+        .collect()
+}
+
+fn texts(renderer: &HeadlessRenderer) -> Vec<String> {
+    renderer
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            DrawCommand::DrawText { text, .. } => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn an_empty_bar_is_drawn_before_the_manifest_is_read() {
+    let renderer = HeadlessRenderer::new();
+
+    draw_loading_scene(&renderer, &LoadProgress::new(), false).unwrap();
+
+    let bar = fill_rects(&renderer);
+    assert_eq!(bar[1].width, 400);
+    assert_eq!(bar[2].width, 0);
+    assert!(texts(&renderer).contains(&"Loading".to_string()));
+}
+
+#[test]
+fn the_bar_fills_and_names_the_asset_being_fetched() {
+    let manifest = AssetManifest::from_json(MANIFEST).unwrap();
+    let mut assets = AssetManager::new();
+    let progress = LoadProgress::new();
+    let renderer = HeadlessRenderer::new();
+
+    let finished = assets
+        .load(&manifest, &StalledLoader, &progress)
+        .now_or_never();
+    draw_loading_scene(&renderer, &progress, false).unwrap();
+
+    assert!(finished.is_none());
+    assert_eq!(fill_rects(&renderer)[2].width, 267);
+    assert!(texts(&renderer).contains(&"Loading background_song.mp3".to_string()));
+}
+
+#[test]
+fn a_failed_load_says_so_instead_of_naming_an_asset() {
+    let renderer = HeadlessRenderer::new();
+
+    draw_loading_scene(&renderer, &LoadProgress::new(), true).unwrap();
+
+    assert!(texts(&renderer).contains(&"Loading failed".to_string()));
+}
+
+#[test]
+fn the_error_scene_lists_each_failed_asset_with_a_retry_button() {
+    let err: anyhow::Error = AssetErrors(vec![AssetError {
+        key: "stone".to_string(),
+        source: "Stone.png".to_string(),
+        message: "<404> Not Found".to_string(),
+    }])
+    .into();
+
+    let html = load_error_ui(&err);
+
+    assert!(html.contains("<li>stone (Stone.png): &lt;404&gt; Not Found</li>"));
+    assert!(html.contains("<button id='retry'>Retry</button>"));
+}
+
+#[test]
+fn other_errors_are_shown_as_they_are() {
+    let html = load_error_ui(&anyhow!("Could not fetch assets.json"));
+
+    assert!(html.contains("<li>Could not fetch assets.json</li>"));
+}
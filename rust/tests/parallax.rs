@@ -0,0 +1,117 @@
+use anyhow::anyhow;
+use rust_webpack_template::engine::{Camera, DrawCommand, HeadlessRenderer, ImageHandle, Point};
+use rust_webpack_template::parallax::{Background, BackgroundFile};
+
+const LAYERS: &str = r#"{
+    "layers": [
+        { "image": "sky", "scroll_factor": 0 },
+        { "image": "hills", "scroll_factor": 0.5, "y": 200 },
+        { "image": "foliage", "scroll_factor": 1.5, "y": 500, "foreground": true },
+        { "image": "sign", "scroll_factor": 1, "tile": false }
+    ]
+}"#;
+
+fn background(json: &str) -> Background {
+    Background::new(&BackgroundFile::from_json(json).unwrap(), |key| {
+        Ok(ImageHandle::headless(&format!("{}.png", key), 400, 100))
+    })
+    .unwrap()
+}
+
+fn drawn(draw: impl Fn(&HeadlessRenderer)) -> Vec<(String, Point)> {
+    let renderer = HeadlessRenderer::new();
+    draw(&renderer);
+    renderer
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            DrawCommand::DrawEntireImage { image, position } => Some((image, position)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn each_layer_follows_the_camera_at_its_own_rate() {
+    let background = background(LAYERS);
+    let camera = Camera::at(Point { x: 40, y: -100 });
+
+    let positions: Vec<Point> = background
+        .layers()
+        .iter()
+        .map(|layer| layer.position(&camera))
+        .collect();
+
+    assert_eq!(
+        positions,
+        vec![
+            Point { x: 0, y: 0 },
+            Point { x: 380, y: 250 },
+            Point { x: 340, y: 650 },
+            Point { x: -40, y: 100 },
+        ]
+    );
+}
+
+#[test]
+fn tiled_layers_cover_the_view_and_untiled_ones_draw_once() {
+    let background = background(LAYERS);
+    let camera = Camera::at(Point { x: 100, y: 0 });
+
+    let behind = drawn(|renderer| background.draw_behind(renderer, &camera).unwrap());
+
+    assert_eq!(
+        behind,
+        vec![
+            ("sky.png".to_string(), Point { x: 0, y: 0 }),
+            ("sky.png".to_string(), Point { x: 400, y: 0 }),
+            ("hills.png".to_string(), Point { x: -50, y: 200 }),
+            ("hills.png".to_string(), Point { x: 350, y: 200 }),
+            ("sign.png".to_string(), Point { x: -100, y: 0 }),
+        ]
+    );
+}
+
+#[test]
+fn foreground_layers_are_drawn_separately() {
+    let background = background(LAYERS);
+
+    let in_front = drawn(|renderer| background.draw_in_front(renderer, &Camera::new()).unwrap());
+
+    assert_eq!(
+        in_front,
+        vec![
+            ("foliage.png".to_string(), Point { x: 0, y: 500 }),
+            ("foliage.png".to_string(), Point { x: 400, y: 500 }),
+        ]
+    );
+}
+
+#[test]
+fn every_bad_layer_is_reported() {
+    let file = BackgroundFile::from_json(
+        r#"{"layers": [
+            { "image": "sky", "scroll_factor": -1 },
+            { "image": "missing" }
+        ]}"#,
+    )
+    .unwrap();
+
+    let err = Background::new(&file, |key| match key {
+        "sky" => Ok(ImageHandle::headless("sky.png", 400, 100)),
+        _ => Err(anyhow!("Asset {} is not loaded", key)),
+    })
+    .err()
+    .unwrap()
+    .to_string();
+
+    assert!(err.contains("layer 1: the scroll factor must be 0 or more"));
+    assert!(err.contains("layer 2: Asset missing is not loaded"));
+}
+
+#[test]
+fn the_shipped_background_loads() {
+    let file = BackgroundFile::from_json(include_str!("../static/background.json")).unwrap();
+
+    assert!(Background::new(&file, |key| Ok(ImageHandle::headless(key, 1000, 750))).is_ok());
+}
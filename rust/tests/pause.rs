@@ -0,0 +1,138 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{
+    Audio, DrawCommand, Game, GameLoop, HeadlessRenderer, KeyState, FOCUS_LOST,
+};
+use rust_webpack_template::game::{Walk, WalkTheDog};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+
+// The game on the shipped course, so a pause has something to freeze.
+fn game() -> WalkTheDog {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    WalkTheDog::from(Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    ))
+}
+
+fn frame(game: &mut WalkTheDog, keys: &[&str]) -> Vec<DrawCommand> {
+    let mut keystate = KeyState::new();
+    keys.iter().for_each(|code| keystate.set_pressed(code));
+    game.update(&keystate).unwrap();
+    let renderer = HeadlessRenderer::new();
+    game.draw(&renderer).unwrap();
+    renderer.take_commands()
+}
+
+fn is_paused(commands: &[DrawCommand]) -> bool {
+    commands
+        .iter()
+        .any(|command| matches!(command, DrawCommand::DrawText { text, .. } if text == "Paused"))
+}
+
+fn walking_game() -> WalkTheDog {
+    let mut game = game();
+    (0..10).for_each(|_| {
+        frame(&mut game, &["ArrowRight"]);
+    });
+    game
+}
+
+#[test]
+fn escape_freezes_the_walk_until_pressed_again() {
+    let mut game = walking_game();
+
+    let paused = frame(&mut game, &["Escape"]);
+    frame(&mut game, &[]);
+    let still_paused = frame(&mut game, &["ArrowRight"]);
+
+    assert!(is_paused(&paused));
+    assert_eq!(paused, still_paused);
+
+    let resumed = frame(&mut game, &["Escape"]);
+    let moved = frame(&mut game, &[]);
+
+    assert!(!is_paused(&resumed));
+    assert_ne!(resumed, moved);
+}
+
+#[test]
+fn holding_the_pause_key_does_not_flicker() {
+    let mut game = walking_game();
+
+    let frames: Vec<bool> = (0..10)
+        .map(|_| is_paused(&frame(&mut game, &["KeyP"])))
+        .collect();
+
+    assert!(frames.iter().all(|paused| *paused));
+}
+
+#[test]
+fn losing_focus_pauses_but_never_resumes() {
+    let mut game = walking_game();
+
+    assert!(is_paused(&frame(&mut game, &[FOCUS_LOST])));
+    frame(&mut game, &[]);
+    assert!(is_paused(&frame(&mut game, &[FOCUS_LOST])));
+    assert!(!is_paused(&frame(&mut game, &["Escape"])));
+}
+
+#[test]
+fn the_paused_overlay_is_drawn_over_the_walk() {
+    let mut game = walking_game();
+
+    let commands = frame(&mut game, &["Escape"]);
+    let overlay = commands
+        .iter()
+        .position(|command| matches!(command, DrawCommand::FillRect { .. }))
+        .unwrap();
+
+    assert!(commands[..overlay]
+        .iter()
+        .any(|command| matches!(command, DrawCommand::DrawImage { .. })));
+    assert!(is_paused(&commands[overlay..]));
+}
+
+#[test]
+fn the_game_loop_runs_one_update_per_frame_at_60_hz() {
+    let mut game_loop = GameLoop::new(0.0);
+
+    assert_eq!(game_loop.advance(17.0), 1);
+    assert_eq!(game_loop.advance(34.0), 1);
+    assert_eq!(game_loop.advance(40.0), 0);
+}
+
+#[test]
+fn a_long_stall_does_not_fast_forward_the_game() {
+    let mut game_loop = GameLoop::new(0.0);
+
+    let updates = game_loop.advance(30_000.0) + game_loop.advance(30_017.0);
+
+    assert!(updates <= 4);
+}
+
+#[test]
+fn a_long_stall_catches_up_exactly_three_updates() {
+    let mut game_loop = GameLoop::new(0.0);
+
+    assert_eq!(game_loop.advance(30_000.0), 3);
+    assert_eq!(game_loop.advance(30_010.0), 0);
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{
+    Audio, Camera, DrawCommand, HeadlessRenderer, Image, ImageHandle, Point, Rect, Renderer,
+};
+use rust_webpack_template::game::Walk;
+use rust_webpack_template::obstacles::{
+    Barrier, Obstacle, Platform, FLOATING_PLATFORM_BOUNDING_BOXES, FLOATING_PLATFORM_SPRITES,
+};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+
+// The opening of the shipped course: a stone and a platform in view.
+fn walk() -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    )
+}
+
+#[test]
+fn clear_is_recorded() {
+    let renderer = HeadlessRenderer::new();
+    renderer.clear(&Rect::new_from_x_y(0, 0, 600, 570));
+
+    assert_eq!(
+        renderer.commands(),
+        vec![DrawCommand::Clear(Rect::new_from_x_y(0, 0, 600, 570))]
+    );
+}
+
+#[test]
+fn image_draws_at_its_position() {
+    let renderer = HeadlessRenderer::new();
+    let image = Image::new(
+        ImageHandle::headless("Stone.png", 90, 54),
+        Point { x: 150, y: 520 },
+    );
+
+    image.draw(&renderer).unwrap();
+
+    assert_eq!(
+        renderer.commands(),
+        vec![DrawCommand::DrawEntireImage {
+            image: "Stone.png".to_string(),
+            position: Point { x: 150, y: 520 },
+        }]
+    );
+}
+
+#[test]
+fn barrier_draws_its_image_where_the_camera_shows_it() {
+    let renderer = HeadlessRenderer::new();
+    let barrier = Barrier::new(Image::new(
+        ImageHandle::headless("Stone.png", 90, 54),
+        Point { x: 150, y: 520 },
+    ));
+    let camera = Camera::at(Point { x: 4, y: 0 });
+
+    barrier.draw(&camera.view(&renderer)).unwrap();
+
+    assert_eq!(
+        renderer.commands(),
+        vec![DrawCommand::DrawEntireImage {
+            image: "Stone.png".to_string(),
+            position: Point { x: 146, y: 520 },
+        }]
+    );
+}
+
+#[test]
+fn platform_draws_each_tile_side_by_side() {
+    let renderer = HeadlessRenderer::new();
+    let platform = Platform::new(
+        common::tiles(),
+        Point { x: 200, y: 420 },
+        &FLOATING_PLATFORM_SPRITES,
+        &FLOATING_PLATFORM_BOUNDING_BOXES,
+    );
+
+    platform.draw(&renderer).unwrap();
+
+    assert_eq!(
+        renderer.take_commands(),
+        vec![
+            DrawCommand::DrawImage {
+                image: "tiles.png".to_string(),
+                frame: Rect::new_from_x_y(261, 391, 128, 93),
+                destination: Rect::new_from_x_y(200, 420, 128, 93),
+            },
+            DrawCommand::DrawImage {
+                image: "tiles.png".to_string(),
+                frame: Rect::new_from_x_y(391, 391, 128, 93),
+                destination: Rect::new_from_x_y(328, 420, 128, 93),
+            },
+            DrawCommand::DrawImage {
+                image: "tiles.png".to_string(),
+                frame: Rect::new_from_x_y(521, 391, 128, 93),
+                destination: Rect::new_from_x_y(456, 420, 128, 93),
+            },
+        ]
+    );
+    assert!(renderer.commands().is_empty());
+}
+
+#[test]
+fn boy_draws_the_current_animation_frame() {
+    let renderer = HeadlessRenderer::new();
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+
+    boy.draw(&renderer).unwrap();
+
+    assert_eq!(
+        renderer.commands(),
+        vec![DrawCommand::DrawImage {
+            image: "rhb.png".to_string(),
+            frame: Rect::new_from_x_y(117, 122, 71, 115),
+            destination: Rect::new_from_x_y(38, 457, 71, 115),
+        }]
+    );
+}
+
+#[test]
+fn walk_draws_backgrounds_then_boy_then_obstacles() {
+    let renderer = HeadlessRenderer::new();
+    let walk = walk();
+
+    walk.draw(&renderer).unwrap();
+
+    let images: Vec<String> = renderer
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            DrawCommand::DrawImage { image, .. } => Some(image),
+            DrawCommand::DrawEntireImage { image, .. } => Some(image),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        images,
+        vec![
+            "BG.png",
+            "rhb.png",
+            "Stone.png",
+            "tiles.png",
+            "tiles.png",
+            "tiles.png"
+        ]
+    );
+}
+
+#[test]
+fn walk_draws_the_score_on_top() {
+    let renderer = HeadlessRenderer::new();
+    let walk = walk();
+
+    walk.draw(&renderer).unwrap();
+
+    assert!(matches!(
+        renderer.commands().last(),
+        Some(DrawCommand::DrawText { text, .. }) if text == "0 m  Score 0"
+    ));
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::engine::{
+    click_code, Audio, DrawCommand, Game, HeadlessRenderer, KeyState,
+};
+use rust_webpack_template::game::{self, Walk, WalkTheDog};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::replay::{InputMode, InputRecorder, Recording, Replay};
+use rust_webpack_template::segments::SegmentPool;
+
+// The whole game as shipped, curve included, so a replay covers all of it.
+fn walk_with_seed(seed: u32) -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(common::difficulty_curve()),
+        seed,
+    )
+}
+
+fn keystate(codes: &[&str]) -> KeyState {
+    let mut keystate = KeyState::new();
+    codes.iter().for_each(|code| keystate.set_pressed(code));
+    keystate
+}
+
+fn pressed(keystate: &KeyState) -> Vec<String> {
+    let mut keys: Vec<String> = keystate.pressed_keys().map(str::to_string).collect();
+    keys.sort();
+    keys
+}
+
+fn script(tick: u32) -> KeyState {
+    match tick {
+        0..=9 => keystate(&[]),
+        40..=45 | 200..=203 => keystate(&["ArrowRight", "Space"]),
+        120..=125 => keystate(&["ArrowRight", "ArrowDown"]),
+        _ => keystate(&["ArrowRight"]),
+    }
+}
+
+fn play(
+    seed: u32,
+    mut input: InputMode,
+    live: impl Fn(u32) -> KeyState,
+    ticks: u32,
+) -> Vec<DrawCommand> {
+    let mut game = WalkTheDog::from(walk_with_seed(seed));
+    let renderer = HeadlessRenderer::new();
+    (0..ticks).for_each(|tick| {
+        game.update(&input.next_keystate(&live(tick))).unwrap();
+        game.draw(&renderer).unwrap();
+    });
+    renderer.take_commands()
+}
+
+#[test]
+fn recording_round_trips_through_json() {
+    let recorder = InputRecorder::new(7);
+    (0..300).for_each(|tick| recorder.record(&script(tick)));
+
+    let json = recorder.recording().to_json().unwrap();
+    let recording = Recording::from_json(&json).unwrap();
+
+    assert_eq!(recording, recorder.recording());
+    assert_eq!(recording.seed, 7);
+    assert_eq!(recording.ticks(), 300);
+    assert!(json.len() < 200);
+}
+
+#[test]
+fn replay_yields_the_recorded_key_sets_then_stops() {
+    let recorder = InputRecorder::new(7);
+    (0..300).for_each(|tick| recorder.record(&script(tick)));
+
+    let replayed: Vec<Vec<String>> = Replay::new(recorder.recording())
+        .map(|keystate| pressed(&keystate))
+        .collect();
+
+    let expected: Vec<Vec<String>> = (0..300).map(|tick| pressed(&script(tick))).collect();
+    assert_eq!(replayed, expected);
+}
+
+#[test]
+fn replay_reproduces_the_recorded_session_frame_for_frame() {
+    let recorder = InputRecorder::new(99);
+    let recorded = play(99, InputMode::Record(recorder.clone()), script, 600);
+
+    let recording = Recording::from_json(&recorder.recording().to_json().unwrap()).unwrap();
+    let replay = Replay::new(recording);
+    let replayed = play(
+        replay.seed(),
+        InputMode::Replay(replay),
+        |_tick| keystate(&[]),
+        600,
+    );
+
+    assert_eq!(recorded, replayed);
+}
+
+#[test]
+fn a_replay_clicks_new_game_on_the_recorded_tick() {
+    // Walk::reset picks the next walk's seed, as a replay would pin it.
+    game::set_seed(99);
+    let new_game = click_code("new_game");
+    let script = |tick: u32| match tick {
+        0..=9 | 101..=109 => keystate(&[]),
+        100 => keystate(&[&new_game]),
+        _ => keystate(&["ArrowRight"]),
+    };
+    let recorder = InputRecorder::new(99);
+    let recorded = play(99, InputMode::Record(recorder.clone()), script, 200);
+
+    let recording = Recording::from_json(&recorder.recording().to_json().unwrap()).unwrap();
+    let replay = Replay::new(recording);
+    let replayed = play(
+        replay.seed(),
+        InputMode::Replay(replay),
+        |_tick| keystate(&[]),
+        200,
+    );
+    let never_clicked = play(
+        99,
+        InputMode::Live,
+        |tick| match tick {
+            100 => keystate(&[]),
+            _ => script(tick),
+        },
+        200,
+    );
+
+    assert_eq!(recorded, replayed);
+    assert_ne!(replayed, never_clicked);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::Audio;
+use rust_webpack_template::game::Walk;
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::score::{Score, OBSTACLE_BONUS};
+use rust_webpack_template::segments::SegmentPool;
+
+// The shipped course, which opens with a stone, at the starting speed.
+fn walk() -> Walk {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    )
+}
+
+#[test]
+fn distance_is_counted_in_metres() {
+    let mut score = Score::new();
+
+    (0..100).for_each(|_| score.add_distance(4));
+
+    assert_eq!(score.distance(), 8);
+    assert_eq!(score.total(), 8);
+}
+
+#[test]
+fn standing_still_or_going_backwards_scores_nothing() {
+    let mut score = Score::new();
+
+    score.add_distance(0);
+    score.add_distance(-4);
+
+    assert_eq!(score, Score::new());
+}
+
+#[test]
+fn cleared_obstacles_add_a_bonus() {
+    let mut score = Score::new();
+    score.add_distance(100);
+    score.obstacle_cleared();
+    score.obstacle_cleared();
+
+    assert_eq!(score.cleared(), 2);
+    assert_eq!(score.total(), 2 + 2 * OBSTACLE_BONUS);
+}
+
+#[test]
+fn jumping_the_first_stone_scores_a_bonus() {
+    let mut walk = walk();
+    walk.boy_mut().run_right();
+    walk.boy_mut().jump();
+
+    (0..120).for_each(|_| walk.update());
+
+    assert!(!walk.knocked_out());
+    assert!(walk.score().cleared() >= 1);
+    assert_eq!(walk.score().distance(), 120 * 4 / 50);
+}
+
+#[test]
+fn running_into_the_first_stone_scores_only_distance() {
+    let mut walk = walk();
+    walk.boy_mut().run_right();
+
+    (0..120).for_each(|_| walk.update());
+
+    assert_eq!(walk.score().cleared(), 0);
+    assert_eq!(walk.score().total(), walk.score().distance());
+}
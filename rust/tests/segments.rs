@@ -0,0 +1,133 @@
+mod common;
+
+use anyhow::anyhow;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rust_webpack_template::segments::{SegmentFile, SegmentPool};
+
+fn pool(json: &str) -> anyhow::Result<SegmentPool> {
+    SegmentPool::new(
+        SegmentFile::from_json(json)?,
+        |key| match key {
+            "stone" => Ok(common::stone()),
+            _ => Err(anyhow!("Asset {} is not loaded", key)),
+        },
+        common::tiles(),
+    )
+}
+
+fn barrier_segment(name: &str, weight: u32) -> String {
+    segment_at_difficulty(name, weight, 0)
+}
+
+fn segment_at_difficulty(name: &str, weight: u32, difficulty: u32) -> String {
+    format!(
+        r#"{{ "name": "{}", "weight": {}, "difficulty": {}, "obstacles": [{{ "kind": "barrier", "image": "stone", "x": 0, "y": 520 }}] }}"#,
+        name, weight, difficulty
+    )
+}
+
+#[test]
+fn the_shipped_segments_place_obstacles_after_the_offset() {
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    )
+    .unwrap();
+
+    let obstacles = segments.create(0, 1000);
+
+    assert_eq!(segments.len(), 4);
+    assert_eq!(segments.name(0), Some("stone_and_platform"));
+    let rights: Vec<i32> = obstacles.iter().map(|obstacle| obstacle.right()).collect();
+    assert_eq!(rights, [1000 + 150 + 90, 1000 + 200 + 384]);
+}
+
+#[test]
+fn weights_default_to_one() {
+    let file = SegmentFile::from_json(
+        r#"{ "segments": [{ "name": "stone", "obstacles": [{ "kind": "barrier", "image": "stone", "x": 0, "y": 520 }] }] }"#,
+    )
+    .unwrap();
+
+    assert_eq!(file.segments[0].weight, 1);
+}
+
+#[test]
+fn segments_are_picked_in_proportion_to_their_weight() {
+    let segments = pool(&format!(
+        r#"{{ "segments": [{}, {}, {}] }}"#,
+        barrier_segment("start", 0),
+        barrier_segment("common", 3),
+        barrier_segment("rare", 1)
+    ))
+    .unwrap();
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut picks = [0; 3];
+
+    (0..4000).for_each(|_| picks[segments.choose(&mut rng, 0)] += 1);
+
+    assert_eq!(picks[0], 0);
+    assert!((2800..3200).contains(&picks[1]), "{:?}", picks);
+    assert!((800..1200).contains(&picks[2]), "{:?}", picks);
+}
+
+#[test]
+fn unknown_sprites_and_images_are_rejected_at_load() {
+    let err = pool(
+        r#"{ "segments": [{ "name": "broken", "obstacles": [
+            { "kind": "barrier", "image": "boulder", "x": 0, "y": 520 },
+            { "kind": "platform", "x": 0, "y": 400, "sprites": ["99.png"],
+              "bounding_boxes": [{ "x": 0, "y": 0, "width": 0, "height": 10 }] }
+        ] }] }"#,
+    )
+    .err()
+    .unwrap()
+    .to_string();
+
+    assert!(
+        err.contains("segment broken: Asset boulder is not loaded"),
+        "{}",
+        err
+    );
+    assert!(
+        err.contains("sprite 99.png is not in the tile sheet"),
+        "{}",
+        err
+    );
+    assert!(err.contains("has an empty bounding box"), "{}", err);
+}
+
+#[test]
+fn a_pool_needs_a_segment_with_weight_and_obstacles() {
+    assert!(pool(r#"{ "segments": [] }"#).is_err());
+    assert!(pool(&format!(
+        r#"{{ "segments": [{}] }}"#,
+        barrier_segment("idle", 0)
+    ))
+    .is_err());
+    assert!(pool(r#"{ "segments": [{ "name": "empty", "obstacles": [] }] }"#).is_err());
+}
+
+#[test]
+fn unknown_obstacle_kinds_do_not_parse() {
+    assert!(SegmentFile::from_json(
+        r#"{ "segments": [{ "name": "pit", "obstacles": [{ "kind": "pit", "x": 0 }] }] }"#
+    )
+    .is_err());
+}
+
+#[test]
+fn harder_segments_wait_until_they_are_unlocked() {
+    let segments = pool(&format!(
+        r#"{{ "segments": [{}, {}] }}"#,
+        segment_at_difficulty("easy", 1, 0),
+        segment_at_difficulty("hard", 1, 2)
+    ))
+    .unwrap();
+    let mut rng = StdRng::seed_from_u64(7);
+
+    assert!((0..100).all(|_| segments.choose(&mut rng, 1) == 0));
+    assert!((0..100).any(|_| segments.choose(&mut rng, 2) == 1));
+}
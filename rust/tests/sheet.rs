@@ -0,0 +1,117 @@
+use rust_webpack_template::engine::{
+    DrawCommand, HeadlessRenderer, ImageHandle, Point, Rect, Sheet, SheetSize, SpriteSheet,
+};
+
+const ARRAY_SHEET: &str = r#"{
+    "frames": [
+        {
+            "filename": "Upright.png",
+            "frame": {"x":0,"y":0,"w":40,"h":20},
+            "rotated": false,
+            "trimmed": true,
+            "spriteSourceSize": {"x":5,"y":6,"w":40,"h":20},
+            "sourceSize": {"w":50,"h":30}
+        },
+        {
+            "filename": "Turned.png",
+            "frame": {"x":40,"y":0,"w":40,"h":20},
+            "rotated": true,
+            "trimmed": false,
+            "spriteSourceSize": {"x":0,"y":0,"w":40,"h":20},
+            "sourceSize": {"w":40,"h":20}
+        }
+    ],
+    "meta": {"image": "packed.png", "size": {"w":60,"h":40}, "scale": "1"}
+}"#;
+
+fn sprite_sheet(json: &str) -> SpriteSheet {
+    let sheet: Sheet = serde_json::from_str(json).unwrap();
+    SpriteSheet::new(sheet, ImageHandle::headless("packed.png", 60, 40))
+}
+
+#[test]
+fn the_shipped_sheets_read_their_meta() {
+    let sheet: Sheet = serde_json::from_str(include_str!("../static/rhb.json")).unwrap();
+
+    assert_eq!(sheet.meta.image.as_deref(), Some("rhb_trimmed.png"));
+    assert_eq!(sheet.meta.scale, 1.0);
+    let idle = &sheet.frames["Idle (1).png"];
+    assert!(idle.trimmed);
+    assert!(!idle.rotated);
+    assert_eq!(idle.source_size(), SheetSize { w: 160, h: 136 });
+}
+
+#[test]
+fn array_sheets_are_keyed_by_filename() {
+    let sheet: Sheet = serde_json::from_str(ARRAY_SHEET).unwrap();
+
+    assert_eq!(sheet.frames.len(), 2);
+    assert!(sheet.frames["Turned.png"].rotated);
+    assert_eq!(sheet.meta.image.as_deref(), Some("packed.png"));
+    assert_eq!(sheet.meta.size, Some(SheetSize { w: 60, h: 40 }));
+}
+
+#[test]
+fn trimmed_frames_are_drawn_at_their_offset() {
+    let sheet = sprite_sheet(ARRAY_SHEET);
+    let renderer = HeadlessRenderer::new();
+
+    sheet
+        .draw(
+            &renderer,
+            sheet.cell("Upright.png").unwrap(),
+            &Point { x: 100, y: 200 },
+        )
+        .unwrap();
+
+    assert_eq!(
+        renderer.commands(),
+        vec![DrawCommand::DrawImage {
+            image: "packed.png".to_string(),
+            frame: Rect::new_from_x_y(0, 0, 40, 20),
+            destination: Rect::new_from_x_y(105, 206, 40, 20),
+        }]
+    );
+}
+
+#[test]
+fn rotated_frames_cover_the_turned_atlas_area_and_draw_upright() {
+    let sheet = sprite_sheet(ARRAY_SHEET);
+    let renderer = HeadlessRenderer::new();
+
+    sheet
+        .draw(
+            &renderer,
+            sheet.cell("Turned.png").unwrap(),
+            &Point { x: 10, y: 10 },
+        )
+        .unwrap();
+
+    assert_eq!(
+        renderer.commands(),
+        vec![DrawCommand::DrawRotatedImage {
+            image: "packed.png".to_string(),
+            frame: Rect::new_from_x_y(40, 0, 20, 40),
+            destination: Rect::new_from_x_y(10, 10, 40, 20),
+        }]
+    );
+}
+
+#[test]
+fn a_sheet_packed_at_double_scale_draws_at_half_size() {
+    let sheet = sprite_sheet(&ARRAY_SHEET.replace(r#""scale": "1""#, r#""scale": 2"#));
+    let cell = sheet.cell("Upright.png").unwrap();
+
+    assert_eq!(
+        sheet.destination(cell, &Point { x: 0, y: 0 }),
+        Rect::new_from_x_y(3, 3, 20, 10)
+    );
+    assert_eq!(sheet.source_size(cell), SheetSize { w: 25, h: 15 });
+}
+
+#[test]
+fn a_scale_of_zero_is_rejected() {
+    let json = ARRAY_SHEET.replace(r#""scale": "1""#, r#""scale": "0""#);
+
+    assert!(serde_json::from_str::<Sheet>(&json).is_err());
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::engine::{Audio, DrawCommand, Game, HeadlessRenderer, KeyState};
+use rust_webpack_template::game::{set_seed, Walk, WalkTheDog};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+
+fn boy(audio: Audio) -> RedHatBoy {
+    RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        audio,
+        common::jump_sound(),
+    )
+}
+
+// The whole game as shipped, curve included.
+fn walk_with_seed(seed: u32) -> Walk {
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    Walk::new(
+        boy(Audio::headless()),
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(common::difficulty_curve()),
+        seed,
+    )
+}
+
+fn walk() -> Walk {
+    walk_with_seed(0)
+}
+
+#[test]
+fn idle_boy_stays_put() {
+    let mut boy = boy(Audio::headless());
+    let starting_y = boy.pos_y();
+
+    (0..1000).for_each(|_| boy.update());
+
+    assert_eq!(boy.walking_speed(), 0);
+    assert_eq!(boy.pos_y(), starting_y);
+    assert!(!boy.knocked_out());
+}
+
+#[test]
+fn jumping_plays_the_jump_sound_and_lands_on_the_floor() {
+    let audio = Audio::headless();
+    let mut boy = boy(audio.clone());
+    let floor = boy.pos_y();
+    boy.run_right();
+    boy.jump();
+    boy.update();
+
+    assert!(boy.pos_y() < floor);
+    assert_eq!(audio.played(), vec!["SFX_Jump_23.mp3"]);
+
+    (0..100).for_each(|_| boy.update());
+
+    assert_eq!(boy.pos_y(), floor);
+}
+
+#[test]
+fn running_into_the_first_stone_knocks_the_boy_out() {
+    let mut walk = walk();
+    walk.boy_mut().run_right();
+
+    let frames = (0..1000).take_while(|_| {
+        walk.update();
+        !walk.knocked_out()
+    });
+
+    assert!(frames.count() < 1000);
+    assert_eq!(walk.boy().walking_speed(), 0);
+}
+
+#[test]
+fn walk_runs_thousands_of_frames_natively() {
+    let mut walk = walk();
+    let renderer = HeadlessRenderer::new();
+    walk.boy_mut().run_right();
+
+    (0..5000).for_each(|_| {
+        walk.update();
+        walk.draw(&renderer).unwrap();
+    });
+
+    assert!(walk.obstacle_count() > 0);
+    assert!(!renderer.take_commands().is_empty());
+}
+
+#[test]
+fn walk_the_dog_runs_natively_through_game_over() {
+    let mut game = WalkTheDog::from(walk());
+    let renderer = HeadlessRenderer::new();
+    let mut keystate = KeyState::new();
+    keystate.set_pressed("ArrowRight");
+
+    (0..5000).for_each(|_| {
+        game.update(&keystate).unwrap();
+        game.draw(&renderer).unwrap();
+    });
+
+    assert!(!renderer.take_commands().is_empty());
+}
+
+fn course(mut walk: Walk) -> Vec<DrawCommand> {
+    let renderer = HeadlessRenderer::new();
+    (0..20).for_each(|_| walk.generate_next_segment());
+    walk.draw(&renderer).unwrap();
+    renderer.take_commands()
+}
+
+#[test]
+fn the_same_seed_generates_the_same_course() {
+    assert_eq!(course(walk_with_seed(42)), course(walk_with_seed(42)));
+    assert_ne!(course(walk_with_seed(42)), course(walk_with_seed(43)));
+}
+
+#[test]
+fn reset_uses_the_overridden_seed() {
+    set_seed(1234);
+
+    let walk = Walk::reset(walk_with_seed(42));
+
+    assert_eq!(walk.seed(), 1234);
+    assert_eq!(course(walk), course(walk_with_seed(1234)));
+}
@@ -0,0 +1,269 @@
+mod common;
+
+use std::rc::Rc;
+
+use rust_webpack_template::difficulty::DifficultyCurve;
+use rust_webpack_template::engine::{
+    Audio, DrawCommand, Game, HeadlessRenderer, KeyState, Point, Rect,
+};
+use rust_webpack_template::game::{Walk, WalkTheDog};
+use rust_webpack_template::parallax::Background;
+use rust_webpack_template::red_hat_boy::RedHatBoy;
+use rust_webpack_template::segments::SegmentPool;
+use rust_webpack_template::touch::{
+    default_buttons, GestureRecognizer, Pointer, PointerKind, SWIPE_DOWN, TAP,
+};
+
+// The game on the shipped course, for comparing taps against keys.
+fn game() -> WalkTheDog {
+    let boy = RedHatBoy::new(
+        common::rhb_sheet(),
+        common::rhb_image(),
+        Audio::headless(),
+        common::jump_sound(),
+    );
+    let segments = SegmentPool::new(
+        common::segment_file(),
+        |_key| Ok(common::stone()),
+        common::tiles(),
+    );
+    WalkTheDog::from(Walk::new(
+        boy,
+        Background::from_image(common::background()),
+        Rc::new(segments.unwrap()),
+        Rc::new(DifficultyCurve::default()),
+        0,
+    ))
+}
+
+fn down(id: i32, kind: PointerKind, x: i32, y: i32, time: f64) -> Pointer {
+    Pointer::Down {
+        id,
+        kind,
+        position: Point { x, y },
+        time,
+    }
+}
+
+fn moved(id: i32, x: i32, y: i32, time: f64) -> Pointer {
+    Pointer::Move {
+        id,
+        position: Point { x, y },
+        time,
+    }
+}
+
+fn up(id: i32, x: i32, y: i32, time: f64) -> Pointer {
+    Pointer::Up {
+        id,
+        position: Point { x, y },
+        time,
+    }
+}
+
+fn codes(gestures: &mut GestureRecognizer, pointers: &[Pointer]) -> Vec<String> {
+    pointers
+        .iter()
+        .for_each(|pointer| gestures.handle(*pointer));
+    let mut keystate = KeyState::new();
+    gestures.apply(&mut keystate);
+    gestures.updated();
+    let mut codes: Vec<String> = keystate.pressed_keys().map(str::to_string).collect();
+    codes.sort();
+    codes
+}
+
+fn recognizer() -> GestureRecognizer {
+    GestureRecognizer::new(default_buttons())
+}
+
+#[test]
+fn a_quick_touch_is_a_tap() {
+    let pointers = [
+        down(1, PointerKind::Touch, 300, 200, 0.0),
+        up(1, 304, 203, 120.0),
+    ];
+
+    assert_eq!(codes(&mut recognizer(), &pointers), [TAP]);
+}
+
+#[test]
+fn a_long_press_or_a_drag_is_not_a_tap() {
+    let long_press = [
+        down(1, PointerKind::Touch, 300, 200, 0.0),
+        up(1, 300, 200, 900.0),
+    ];
+    let sideways = [
+        down(2, PointerKind::Touch, 300, 200, 0.0),
+        moved(2, 380, 220, 80.0),
+        up(2, 400, 230, 150.0),
+    ];
+
+    assert!(codes(&mut recognizer(), &long_press).is_empty());
+    assert!(codes(&mut recognizer(), &sideways).is_empty());
+}
+
+#[test]
+fn a_swipe_down_fires_once_as_soon_as_it_is_recognised() {
+    let mut gestures = recognizer();
+
+    let while_moving = codes(
+        &mut gestures,
+        &[
+            down(1, PointerKind::Touch, 300, 100, 0.0),
+            moved(1, 305, 180, 100.0),
+        ],
+    );
+    let after_lifting = codes(
+        &mut gestures,
+        &[moved(1, 310, 250, 150.0), up(1, 310, 260, 200.0)],
+    );
+
+    assert_eq!(while_moving, [SWIPE_DOWN]);
+    assert!(after_lifting.is_empty());
+}
+
+#[test]
+fn buttons_only_appear_after_a_touch() {
+    let mut gestures = recognizer();
+    let renderer = HeadlessRenderer::new();
+
+    let with_mouse = codes(
+        &mut gestures,
+        &[
+            down(1, PointerKind::Mouse, 520, 510, 0.0),
+            up(1, 520, 510, 50.0),
+        ],
+    );
+    gestures.draw(&renderer).unwrap();
+
+    assert_eq!(with_mouse, [TAP]);
+    assert!(renderer.take_commands().is_empty());
+
+    codes(
+        &mut gestures,
+        &[
+            down(2, PointerKind::Touch, 300, 200, 0.0),
+            up(2, 300, 200, 50.0),
+        ],
+    );
+    gestures.draw(&renderer).unwrap();
+
+    assert!(gestures.buttons_visible());
+    assert!(renderer.take_commands().contains(&DrawCommand::FillRect {
+        rect: Rect::new_from_x_y(472, 474, 112, 80),
+        color: "rgba(255, 255, 255, 0.3)".to_string(),
+    }));
+}
+
+#[test]
+fn buttons_are_held_while_touched() {
+    let mut gestures = recognizer();
+    codes(
+        &mut gestures,
+        &[
+            down(1, PointerKind::Touch, 300, 200, 0.0),
+            up(1, 300, 200, 50.0),
+        ],
+    );
+
+    let pressing = codes(
+        &mut gestures,
+        &[down(2, PointerKind::Touch, 60, 500, 100.0)],
+    );
+    let holding = codes(&mut gestures, &[moved(2, 62, 502, 400.0)]);
+    let released = codes(&mut gestures, &[up(2, 62, 502, 500.0)]);
+
+    assert_eq!(pressing, ["TouchSlide"]);
+    assert_eq!(holding, ["TouchSlide"]);
+    assert!(released.is_empty());
+}
+
+#[test]
+fn a_button_pressed_and_released_within_one_frame_still_counts() {
+    let mut gestures = recognizer();
+    codes(
+        &mut gestures,
+        &[
+            down(1, PointerKind::Touch, 300, 200, 0.0),
+            up(1, 300, 200, 50.0),
+        ],
+    );
+
+    let pressed = codes(
+        &mut gestures,
+        &[
+            down(2, PointerKind::Touch, 550, 30, 100.0),
+            up(2, 550, 30, 110.0),
+        ],
+    );
+
+    assert_eq!(pressed, ["TouchPause"]);
+}
+
+#[test]
+fn a_button_stays_pressed_until_an_update_has_seen_it() {
+    let mut gestures = recognizer();
+    codes(
+        &mut gestures,
+        &[
+            down(1, PointerKind::Touch, 300, 200, 0.0),
+            up(1, 300, 200, 50.0),
+        ],
+    );
+    let mut keystate = KeyState::new();
+
+    gestures.handle(down(2, PointerKind::Touch, 550, 30, 100.0));
+    gestures.apply(&mut keystate);
+    gestures.handle(up(2, 550, 30, 110.0));
+    gestures.apply(&mut keystate);
+    let before_update = keystate.is_pressed("TouchPause");
+    gestures.updated();
+    gestures.apply(&mut keystate);
+
+    assert!(before_update);
+    assert!(!keystate.is_pressed("TouchPause"));
+}
+
+#[test]
+fn cancelled_touches_do_nothing() {
+    let pointers = [
+        down(1, PointerKind::Touch, 300, 200, 0.0),
+        Pointer::Cancel { id: 1 },
+        up(1, 300, 200, 50.0),
+    ];
+
+    assert!(codes(&mut recognizer(), &pointers).is_empty());
+}
+
+#[test]
+fn a_tap_starts_the_walk_and_the_next_one_jumps() {
+    let frame = |game: &mut WalkTheDog, keys: &[&str]| {
+        let mut keystate = KeyState::new();
+        keys.iter().for_each(|code| keystate.set_pressed(code));
+        game.update(&keystate).unwrap();
+        let renderer = HeadlessRenderer::new();
+        game.draw(&renderer).unwrap();
+        renderer.take_commands()
+    };
+    let mut tapped = game();
+    let mut keyboard = game();
+
+    let taps: Vec<_> = (0..40)
+        .map(|tick| {
+            frame(
+                &mut tapped,
+                if tick == 0 || tick == 20 { &[TAP] } else { &[] },
+            )
+        })
+        .collect();
+    let keys: Vec<_> = (0..40)
+        .map(|tick| match tick {
+            0 => frame(&mut keyboard, &["ArrowRight"]),
+            20 => frame(&mut keyboard, &["Space"]),
+            _ => frame(&mut keyboard, &[]),
+        })
+        .collect();
+
+    assert_eq!(taps, keys);
+}